@@ -0,0 +1,325 @@
+use borsh::BorshSerialize;
+use nesdie::{env, AccountId};
+
+use crate::lib::{Box, Vec};
+
+const OWNER_TAG: u8 = b'o';
+const PENDING_OWNER_TAG: u8 = b'p';
+const ROLE_TAG: u8 = b'r';
+
+/// Access control for a contract, with a single owner, two-step ownership transfer and role-based
+/// membership. Account ids are compared byte for byte against the predecessor of the call, so the
+/// guards only cost a storage read and abort without a message on failure.
+///
+/// # Example
+/// ```
+/// use nesdie::mock::VmContextBuilder;
+/// use nesdie::testing_env;
+/// use nesdie_store::AccessControl;
+///
+/// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+/// access.init("alice");
+///
+/// testing_env!(VmContextBuilder::new().predecessor_account_id("alice".into()).build());
+/// access.assert_owner();
+/// access.grant_role("minter", "bob");
+///
+/// testing_env!(VmContextBuilder::new().predecessor_account_id("bob".into()).build());
+/// access.assert_role("minter");
+/// ```
+#[derive(Debug)]
+pub struct AccessControl {
+    prefix: Box<[u8]>,
+}
+
+impl AccessControl {
+    /// Creates access control which stores its data under `prefix`.
+    pub fn new(prefix: Box<[u8]>) -> Self {
+        Self { prefix }
+    }
+
+    fn key(&self, tag: u8) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.prefix.len() + 1);
+        key.extend_from_slice(&self.prefix);
+        key.push(tag);
+        key
+    }
+
+    fn role_key(&self, role: &str, account_id: &str) -> Vec<u8> {
+        let mut key = self.key(ROLE_TAG);
+        (role, account_id)
+            .serialize(&mut key)
+            .unwrap_or_else(|_| env::abort());
+        key
+    }
+
+    /// Returns `true` if the stored value under `key` is equal to the predecessor account id.
+    fn predecessor_matches(key: &[u8]) -> bool {
+        let mut buf = [0u8; 64];
+        match env::storage_read(key, &mut buf) {
+            Some(len) => env::predecessor_account_id().as_bytes() == &buf[..len],
+            None => false,
+        }
+    }
+
+    fn read_account(key: &[u8]) -> Option<AccountId> {
+        let mut buf = [0u8; 64];
+        let len = env::storage_read(key, &mut buf)?;
+        let mut account_id = AccountId::new();
+        core::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(|s| account_id.push_str(s).ok())
+            .unwrap_or_else(|| env::abort());
+        Some(account_id)
+    }
+
+    /// Sets the initial owner.
+    ///
+    /// Aborts if an owner has already been set.
+    pub fn init(&self, owner: &str) {
+        let key = self.key(OWNER_TAG);
+        if env::storage_has_key(&key) {
+            env::abort();
+        }
+        env::storage_write(&key, owner.as_bytes());
+    }
+
+    /// Returns the current owner, if one has been set.
+    pub fn owner(&self) -> Option<AccountId> {
+        Self::read_account(&self.key(OWNER_TAG))
+    }
+
+    /// Returns the account that ownership has been proposed to, if any.
+    pub fn pending_owner(&self) -> Option<AccountId> {
+        Self::read_account(&self.key(PENDING_OWNER_TAG))
+    }
+
+    /// Returns `true` if the predecessor of the call is the owner.
+    pub fn is_owner(&self) -> bool {
+        Self::predecessor_matches(&self.key(OWNER_TAG))
+    }
+
+    /// Aborts if the predecessor of the call is not the owner.
+    ///
+    /// ```should_panic
+    /// use nesdie::mock::VmContextBuilder;
+    /// use nesdie::testing_env;
+    /// use nesdie_store::AccessControl;
+    ///
+    /// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+    /// access.init("alice");
+    ///
+    /// testing_env!(VmContextBuilder::new().predecessor_account_id("bob".into()).build());
+    /// access.assert_owner();
+    /// ```
+    pub fn assert_owner(&self) {
+        if !self.is_owner() {
+            env::abort();
+        }
+    }
+
+    /// Proposes `new_owner` as the owner. Ownership is only transferred once the new owner calls
+    /// [`AccessControl::accept_owner`]. Proposing again replaces the previous proposal.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn propose_owner(&self, new_owner: &str) {
+        self.assert_owner();
+        env::storage_write(&self.key(PENDING_OWNER_TAG), new_owner.as_bytes());
+    }
+
+    /// Cancels a pending ownership transfer.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn cancel_owner_proposal(&self) {
+        self.assert_owner();
+        env::storage_remove(&self.key(PENDING_OWNER_TAG));
+    }
+
+    /// Accepts a pending ownership transfer, which makes the predecessor of the call the owner.
+    ///
+    /// Aborts if ownership was not proposed to the predecessor of the call.
+    ///
+    /// # Example
+    /// ```
+    /// use nesdie::mock::VmContextBuilder;
+    /// use nesdie::testing_env;
+    /// use nesdie_store::AccessControl;
+    ///
+    /// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+    /// access.init("alice");
+    ///
+    /// testing_env!(VmContextBuilder::new().predecessor_account_id("alice".into()).build());
+    /// access.propose_owner("bob");
+    /// assert_eq!(access.owner().unwrap(), "alice");
+    ///
+    /// testing_env!(VmContextBuilder::new().predecessor_account_id("bob".into()).build());
+    /// access.accept_owner();
+    /// assert_eq!(access.owner().unwrap(), "bob");
+    /// assert!(access.pending_owner().is_none());
+    /// ```
+    pub fn accept_owner(&self) {
+        let pending_key = self.key(PENDING_OWNER_TAG);
+        if !Self::predecessor_matches(&pending_key) {
+            env::abort();
+        }
+        env::storage_remove(&pending_key);
+        env::storage_write(
+            &self.key(OWNER_TAG),
+            env::predecessor_account_id().as_bytes(),
+        );
+    }
+
+    /// Returns `true` if `account_id` has been granted `role`.
+    pub fn has_role(&self, role: &str, account_id: &str) -> bool {
+        env::storage_has_key(&self.role_key(role, account_id))
+    }
+
+    /// Aborts if the predecessor of the call has not been granted `role`.
+    pub fn assert_role(&self, role: &str) {
+        if !self.has_role(role, env::predecessor_account_id().as_str()) {
+            env::abort();
+        }
+    }
+
+    /// Grants `role` to `account_id`. Returns `true` if the account already had the role.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn grant_role(&self, role: &str, account_id: &str) -> bool {
+        self.assert_owner();
+        env::storage_write(&self.role_key(role, account_id), &[])
+    }
+
+    /// Revokes `role` from `account_id`. Returns `true` if the account had the role.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn revoke_role(&self, role: &str, account_id: &str) -> bool {
+        self.assert_owner();
+        env::storage_remove(&self.role_key(role, account_id))
+    }
+
+    /// Removes `role` from the predecessor of the call. Returns `true` if the account had the role.
+    pub fn renounce_role(&self, role: &str) -> bool {
+        env::storage_remove(&self.role_key(role, env::predecessor_account_id().as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nesdie::mock::{catch_contract_panic, ContractError, VmContextBuilder};
+    use nesdie::testing_env;
+
+    fn set_predecessor(account_id: &str) {
+        testing_env!(VmContextBuilder::new()
+            .predecessor_account_id(account_id.into())
+            .build());
+    }
+
+    fn access() -> AccessControl {
+        let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+        access.init("alice");
+        access
+    }
+
+    #[test]
+    fn init_twice() {
+        set_predecessor("alice");
+        let access = access();
+        assert_eq!(
+            catch_contract_panic(|| access.init("bob")),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(access.owner().unwrap(), "alice");
+    }
+
+    #[test]
+    fn non_owner_grant_and_revoke() {
+        set_predecessor("alice");
+        let access = access();
+        assert!(!access.grant_role("minter", "carol"));
+
+        set_predecessor("bob");
+        assert_eq!(
+            catch_contract_panic(|| access.grant_role("minter", "bob")),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(
+            catch_contract_panic(|| access.revoke_role("minter", "carol")),
+            Err(ContractError::Abort)
+        );
+        assert!(!access.has_role("minter", "bob"));
+        assert!(access.has_role("minter", "carol"));
+    }
+
+    #[test]
+    fn roles() {
+        set_predecessor("alice");
+        let access = access();
+        assert!(!access.grant_role("minter", "bob"));
+        assert!(access.grant_role("minter", "bob"));
+
+        set_predecessor("bob");
+        access.assert_role("minter");
+        assert_eq!(
+            catch_contract_panic(|| access.assert_role("burner")),
+            Err(ContractError::Abort)
+        );
+        assert!(access.renounce_role("minter"));
+        assert_eq!(
+            catch_contract_panic(|| access.assert_role("minter")),
+            Err(ContractError::Abort)
+        );
+
+        set_predecessor("alice");
+        assert!(!access.revoke_role("minter", "bob"));
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        set_predecessor("alice");
+        let access = access();
+        access.propose_owner("bob");
+        assert_eq!(access.pending_owner().unwrap(), "bob");
+
+        // Only the proposed account can accept, and only the owner can propose.
+        set_predecessor("carol");
+        assert_eq!(
+            catch_contract_panic(|| access.accept_owner()),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(
+            catch_contract_panic(|| access.propose_owner("carol")),
+            Err(ContractError::Abort)
+        );
+
+        set_predecessor("bob");
+        access.accept_owner();
+        assert_eq!(access.owner().unwrap(), "bob");
+        assert!(access.pending_owner().is_none());
+        assert!(access.is_owner());
+
+        // The previous owner lost its permissions.
+        set_predecessor("alice");
+        assert!(!access.is_owner());
+        assert_eq!(
+            catch_contract_panic(|| access.grant_role("minter", "alice")),
+            Err(ContractError::Abort)
+        );
+    }
+
+    #[test]
+    fn cancelled_proposal() {
+        set_predecessor("alice");
+        let access = access();
+        access.propose_owner("bob");
+        access.cancel_owner_proposal();
+        assert!(access.pending_owner().is_none());
+
+        set_predecessor("bob");
+        assert_eq!(
+            catch_contract_panic(|| access.accept_owner()),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(access.owner().unwrap(), "alice");
+    }
+}
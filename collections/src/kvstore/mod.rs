@@ -10,6 +10,7 @@ use crate::{
 };
 
 /// A wrapper around the NEAR contract key-value storage.
+#[allow(clippy::type_complexity)]
 pub struct KvStore<K, V, H = Identity> {
     prefix: Box<[u8]>,
     _marker: PhantomData<fn() -> (K, V, H)>,
@@ -46,7 +47,7 @@ where
     /// ```
     pub fn with_hasher(prefix: Box<[u8]>) -> Self {
         Self {
-            prefix: prefix,
+            prefix,
             _marker: Default::default(),
         }
     }
@@ -63,9 +64,9 @@ where
         V::try_from_slice(bytes).unwrap()
     }
 
-    fn storage_key<Q: ?Sized>(&self, key: &Q) -> Vec<u8>
+    fn storage_key<Q>(&self, key: &Q) -> Vec<u8>
     where
-        Q: BorshSerialize,
+        Q: ?Sized + BorshSerialize,
         K: Borrow<Q>,
     {
         let mut buffer = Vec::with_capacity(self.prefix.len());
@@ -94,14 +95,14 @@ where
     /// assert_eq!(map.get(&37).unwrap(), "c".to_string());
     /// ```
     #[inline]
-    pub fn insert<Q: ?Sized, R: ?Sized>(&mut self, key: &Q, value: &R) -> bool
+    pub fn insert<Q, R>(&mut self, key: &Q, value: &R) -> bool
     where
         K: Borrow<Q>,
-        Q: BorshSerialize,
+        Q: ?Sized + BorshSerialize,
         V: Borrow<R>,
-        R: BorshSerialize,
+        R: ?Sized + BorshSerialize,
     {
-        env::storage_write(&self.storage_key(&key), &value.try_to_vec().unwrap())
+        env::storage_write(&self.storage_key(key), &value.try_to_vec().unwrap())
     }
 
     /// Returns the value corresponding to the key.
@@ -121,13 +122,13 @@ where
     /// assert_eq!(map.get(&2), None);
     /// ```
     #[inline]
-    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<V>
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: BorshSerialize,
+        Q: ?Sized + BorshSerialize,
         V: BorshDeserialize,
     {
-        utils::alloc_storage_read(&self.storage_key(&key))
+        utils::alloc_storage_read(&self.storage_key(key))
             .as_deref()
             .map(Self::deserialize_element)
     }
@@ -148,12 +149,12 @@ where
     /// assert_eq!(map.contains_key(&2u32), false);
     /// ```
     #[inline]
-    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         K: Borrow<Q>,
-        Q: BorshSerialize,
+        Q: ?Sized + BorshSerialize,
     {
-        env::storage_has_key(&self.storage_key(&key))
+        env::storage_has_key(&self.storage_key(key))
     }
 
     /// Removes a key from storage, returning the value at the key if the key
@@ -173,14 +174,60 @@ where
     /// assert_eq!(map.remove(&1), None);
     /// ```
     #[inline]
-    pub fn remove<Q: ?Sized>(&self, key: &Q) -> Option<V>
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
     where
         K: Borrow<Q>,
-        Q: BorshSerialize,
+        Q: ?Sized + BorshSerialize,
         V: BorshDeserialize,
     {
-        utils::storage_remove_alloc(&self.storage_key(&key))
+        utils::storage_remove_alloc(&self.storage_key(key))
             .as_deref()
             .map(Self::deserialize_element)
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<K, V> KvStore<K, V, Identity>
+where
+    K: BorshDeserialize,
+    V: BorshDeserialize,
+{
+    /// Decodes the changes of entries of this store in a storage diff of the mocked blockchain.
+    /// Keys under the prefix which can't be decoded, such as keys of another collection with a
+    /// longer prefix, are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use nesdie::mock::with_mocked_blockchain;
+    /// use nesdie_store::testing::Change;
+    /// use nesdie_store::KvStore;
+    ///
+    /// let mut map: KvStore<u32, String> = KvStore::new(b"m".to_vec().into_boxed_slice());
+    /// map.insert(&1, "a");
+    /// let snapshot = with_mocked_blockchain(|b| b.snapshot());
+    ///
+    /// map.insert(&1, "b");
+    /// map.insert(&2, "c");
+    /// let diff = with_mocked_blockchain(|b| b.diff(&snapshot));
+    /// assert_eq!(
+    ///     map.changes(&diff),
+    ///     vec![
+    ///         Change::Modified { key: 1, old: "a".to_string(), new: "b".to_string() },
+    ///         Change::Added { key: 2, value: "c".to_string() },
+    ///     ]
+    /// );
+    /// ```
+    pub fn changes(&self, diff: &nesdie::mock::StorageDiff) -> Vec<crate::testing::Change<K, V>> {
+        diff.with_prefix(&self.prefix)
+            .iter()
+            .filter_map(|(key, change)| {
+                crate::testing::Change::decode(
+                    key,
+                    change,
+                    |key| K::try_from_slice(&key[self.prefix.len()..]).ok(),
+                    |value| V::try_from_slice(value).ok(),
+                )
+            })
+            .collect()
+    }
+}
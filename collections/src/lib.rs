@@ -2,13 +2,24 @@
 
 mod utils;
 
+mod access;
 /// Storage key hash function types and trait to override map hash functions.
 pub mod key;
+pub use access::AccessControl;
 mod kvstore;
 pub use kvstore::KvStore;
+mod pausable;
+pub use pausable::{Pausable, EMERGENCY, PAUSER_ROLE};
+/// Versioned contract state, which is migrated from older layouts when read.
+pub mod state;
+/// Helpers to inspect the storage changes of collections in tests and to compare collections
+/// against reference models.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 
 extern crate alloc;
 
+#[allow(unused_imports)]
 mod lib {
     mod core {
         pub use core::*;
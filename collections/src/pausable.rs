@@ -0,0 +1,305 @@
+use nesdie::env::{self, LogPart};
+
+use crate::lib::{Box, Vec};
+use crate::AccessControl;
+
+/// Role which is allowed to pause features and trigger an emergency stop. Only the owner can
+/// unpause features or resume from an emergency stop.
+pub const PAUSER_ROLE: &str = "pauser";
+
+/// Bit of the paused set which marks an emergency stop. This bit can't be used for a feature.
+pub const EMERGENCY: u64 = 1 << 63;
+
+const PAUSED_TAG: u8 = b'f';
+
+/// Circuit breaker which pauses individual features of a contract. Features are bits of a `u64`,
+/// so up to 63 features can be paused independently, since the highest bit is reserved for
+/// [`EMERGENCY`]. The paused set is stored, and checking it costs a single storage read.
+///
+/// During an emergency stop every feature is paused, except for the features in the emergency
+/// allowlist given on construction, such as withdrawals.
+///
+/// Calls to paused features trap with [`env::abort`], or with [`env::panic_str`] if the
+/// `panic-message` feature is enabled.
+///
+/// # Example
+/// ```
+/// use nesdie::mock::VmContextBuilder;
+/// use nesdie::testing_env;
+/// use nesdie_store::{AccessControl, Pausable};
+///
+/// const TRANSFER: u64 = 1 << 0;
+/// const WITHDRAW: u64 = 1 << 1;
+///
+/// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+/// access.init("alice");
+/// let pausable = Pausable::new(b"p".to_vec().into_boxed_slice(), WITHDRAW);
+///
+/// testing_env!(VmContextBuilder::new().predecessor_account_id("alice".into()).build());
+/// pausable.emergency_stop(&access);
+/// assert!(pausable.is_paused(TRANSFER));
+///
+/// let logs = nesdie::mock::with_mocked_blockchain(|b| b.logs());
+/// assert!(logs[0].contains(r#""event":"pause""#));
+///
+/// // Withdrawals are still allowed during an emergency stop.
+/// pausable.when_not_paused(WITHDRAW);
+/// ```
+#[derive(Debug)]
+pub struct Pausable {
+    prefix: Box<[u8]>,
+    emergency_allowlist: u64,
+}
+
+/// Traps the execution because a paused feature was called.
+fn paused_trap() -> ! {
+    if cfg!(feature = "panic-message") {
+        env::panic_str("paused")
+    } else {
+        env::abort()
+    }
+}
+
+/// Logs a pausable event in the NEP-297 event format.
+fn log_event(event: &str, features: u64) {
+    env::log_parts(&[
+        LogPart::Str(r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":""#),
+        LogPart::Str(event),
+        LogPart::Str(r#"","data":{"features":"#),
+        LogPart::U64(features),
+        LogPart::Str("}}"),
+    ]);
+}
+
+impl Pausable {
+    /// Creates a circuit breaker which stores the paused set under `prefix`. The features in
+    /// `emergency_allowlist` can still be called during an emergency stop.
+    pub fn new(prefix: Box<[u8]>, emergency_allowlist: u64) -> Self {
+        Self {
+            prefix,
+            emergency_allowlist,
+        }
+    }
+
+    fn key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.prefix.len() + 1);
+        key.extend_from_slice(&self.prefix);
+        key.push(PAUSED_TAG);
+        key
+    }
+
+    /// Returns the set of paused features, including the [`EMERGENCY`] bit.
+    pub fn paused(&self) -> u64 {
+        let mut buf = [0u8; 8];
+        match env::storage_read(&self.key(), &mut buf) {
+            Some(8) => u64::from_le_bytes(buf),
+            Some(_) => env::abort(),
+            None => 0,
+        }
+    }
+
+    fn write_paused(&self, paused: u64) {
+        env::storage_write(&self.key(), &paused.to_le_bytes());
+    }
+
+    fn is_paused_in(&self, paused: u64, features: u64) -> bool {
+        paused & features != 0
+            || (paused & EMERGENCY != 0 && self.emergency_allowlist & features != features)
+    }
+
+    /// Returns `true` if any of `features` is paused, either directly or by an emergency stop.
+    pub fn is_paused(&self, features: u64) -> bool {
+        self.is_paused_in(self.paused(), features)
+    }
+
+    /// Traps if any of `features` is paused, either directly or by an emergency stop.
+    ///
+    /// ```should_panic
+    /// use nesdie::mock::VmContextBuilder;
+    /// use nesdie::testing_env;
+    /// use nesdie_store::{AccessControl, Pausable};
+    ///
+    /// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+    /// access.init("alice");
+    /// let pausable = Pausable::new(b"p".to_vec().into_boxed_slice(), 0);
+    ///
+    /// testing_env!(VmContextBuilder::new().predecessor_account_id("alice".into()).build());
+    /// pausable.pause(&access, 1);
+    /// pausable.when_not_paused(1);
+    /// ```
+    pub fn when_not_paused(&self, features: u64) {
+        if self.is_paused(features) {
+            paused_trap();
+        }
+    }
+
+    /// Traps unless all of `features` are paused, either directly or by an emergency stop. Used
+    /// for methods only callable while paused, such as recovery methods.
+    pub fn when_paused(&self, features: u64) {
+        let paused = self.paused();
+        // Each feature has to be paused on its own, so the bits are checked one at a time.
+        let all_paused = (0..64)
+            .map(|i| 1u64 << i)
+            .filter(|bit| features & bit != 0)
+            .all(|bit| self.is_paused_in(paused, bit));
+        if !all_paused {
+            paused_trap();
+        }
+    }
+
+    fn assert_pauser(access: &AccessControl) {
+        if !access.is_owner() {
+            access.assert_role(PAUSER_ROLE);
+        }
+    }
+
+    /// Pauses `features`, and logs a `pause` event.
+    ///
+    /// Aborts if the predecessor of the call is neither the owner nor has the [`PAUSER_ROLE`].
+    pub fn pause(&self, access: &AccessControl, features: u64) {
+        Self::assert_pauser(access);
+        self.write_paused(self.paused() | features);
+        log_event("pause", features);
+    }
+
+    /// Unpauses `features`, and logs an `unpause` event. This does not resume from an emergency
+    /// stop unless [`EMERGENCY`] is included.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn unpause(&self, access: &AccessControl, features: u64) {
+        access.assert_owner();
+        self.write_paused(self.paused() & !features);
+        log_event("unpause", features);
+    }
+
+    /// Pauses all features except the emergency allowlist.
+    ///
+    /// Aborts if the predecessor of the call is neither the owner nor has the [`PAUSER_ROLE`].
+    pub fn emergency_stop(&self, access: &AccessControl) {
+        self.pause(access, EMERGENCY);
+    }
+
+    /// Resumes from an emergency stop. Features which were paused individually stay paused.
+    ///
+    /// Aborts if the predecessor of the call is not the owner.
+    pub fn resume(&self, access: &AccessControl) {
+        self.unpause(access, EMERGENCY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nesdie::mock::{catch_contract_panic, with_mocked_blockchain, VmContextBuilder};
+    use nesdie::testing_env;
+
+    const TRANSFER: u64 = 1 << 0;
+    const MINT: u64 = 1 << 1;
+    const WITHDRAW: u64 = 1 << 2;
+
+    fn set_predecessor(account_id: &str) {
+        testing_env!(VmContextBuilder::new()
+            .predecessor_account_id(account_id.into())
+            .build());
+    }
+
+    fn setup() -> (AccessControl, Pausable) {
+        set_predecessor("alice");
+        let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+        access.init("alice");
+        access.grant_role(PAUSER_ROLE, "bob");
+        let pausable = Pausable::new(b"p".to_vec().into_boxed_slice(), WITHDRAW);
+        (access, pausable)
+    }
+
+    fn last_log() -> String {
+        with_mocked_blockchain(|b| b.logs()).pop().unwrap()
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let (access, pausable) = setup();
+
+        set_predecessor("bob");
+        pausable.pause(&access, TRANSFER | MINT);
+        assert_eq!(pausable.paused(), TRANSFER | MINT);
+        assert!(pausable.is_paused(TRANSFER));
+        assert!(!pausable.is_paused(WITHDRAW));
+        assert!(catch_contract_panic(|| pausable.when_not_paused(MINT)).is_err());
+        pausable.when_not_paused(WITHDRAW);
+
+        // Only the owner can unpause.
+        assert!(catch_contract_panic(|| pausable.unpause(&access, MINT)).is_err());
+        set_predecessor("alice");
+        pausable.unpause(&access, MINT);
+        assert_eq!(pausable.paused(), TRANSFER);
+        pausable.when_not_paused(MINT);
+    }
+
+    #[test]
+    fn only_pausers_can_pause() {
+        let (access, pausable) = setup();
+
+        set_predecessor("carol");
+        assert!(catch_contract_panic(|| pausable.pause(&access, TRANSFER)).is_err());
+        assert!(catch_contract_panic(|| pausable.emergency_stop(&access)).is_err());
+        assert_eq!(pausable.paused(), 0);
+    }
+
+    #[test]
+    fn emergency_allowlist() {
+        let (access, pausable) = setup();
+        pausable.pause(&access, MINT);
+
+        set_predecessor("bob");
+        pausable.emergency_stop(&access);
+        assert!(pausable.is_paused(TRANSFER));
+        assert!(pausable.is_paused(TRANSFER | WITHDRAW));
+        assert!(!pausable.is_paused(WITHDRAW));
+        pausable.when_not_paused(WITHDRAW);
+        assert!(catch_contract_panic(|| pausable.when_not_paused(TRANSFER)).is_err());
+
+        // Resuming keeps the features which were paused individually.
+        set_predecessor("alice");
+        pausable.resume(&access);
+        assert_eq!(pausable.paused(), MINT);
+        pausable.when_not_paused(TRANSFER);
+    }
+
+    #[test]
+    fn when_paused() {
+        let (access, pausable) = setup();
+        assert!(catch_contract_panic(|| pausable.when_paused(TRANSFER)).is_err());
+
+        pausable.pause(&access, TRANSFER);
+        pausable.when_paused(TRANSFER);
+        assert!(catch_contract_panic(|| pausable.when_paused(TRANSFER | MINT)).is_err());
+
+        // Features paused by the emergency stop count as paused, the allowlist doesn't.
+        pausable.emergency_stop(&access);
+        pausable.when_paused(TRANSFER | MINT);
+        pausable.when_paused(EMERGENCY);
+        assert!(catch_contract_panic(|| pausable.when_paused(MINT | WITHDRAW)).is_err());
+    }
+
+    #[test]
+    fn event_logs() {
+        let (access, pausable) = setup();
+
+        pausable.pause(&access, TRANSFER | MINT);
+        assert_eq!(
+            last_log(),
+            r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":"pause","data":{"features":3}}"#
+        );
+        pausable.unpause(&access, MINT);
+        assert_eq!(
+            last_log(),
+            r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":"unpause","data":{"features":2}}"#
+        );
+        pausable.emergency_stop(&access);
+        assert_eq!(
+            last_log(),
+            r#"EVENT_JSON:{"standard":"pausable","version":"1.0.0","event":"pause","data":{"features":9223372036854775808}}"#
+        );
+    }
+}
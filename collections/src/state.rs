@@ -0,0 +1,305 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use nesdie::env;
+
+use crate::utils;
+
+/// Key used to store the version of the layout of the contract state.
+pub const VERSION_KEY: &[u8] = b"STATE_LAYOUT_VERSION";
+
+/// Migration of contract state from an older version, which decodes the old layout and converts
+/// it into the current state.
+pub struct Migration<T> {
+    /// Version of the state layout that this migration decodes.
+    pub from_version: u32,
+    /// Decodes the old layout from its borsh encoded bytes and converts it to the current state.
+    pub migrate: fn(&[u8]) -> T,
+}
+
+/// Contract state which is stored with a version tag. Reading state written by an older version
+/// dispatches to the registered [`Migration`] for that version.
+///
+/// The version tag is stored under [`VERSION_KEY`], separately from the code version updated by
+/// [`nesdie::upgrade::migrate`], so an upgrade which keeps the state layout doesn't affect how the
+/// state is decoded. State written without a version tag, such as through
+/// [`env::state_write_raw`], is read as version `0`.
+///
+/// # Example
+/// ```
+/// use borsh::{BorshDeserialize, BorshSerialize};
+/// use nesdie_store::state::{self, Migration, VersionedState};
+///
+/// #[derive(BorshSerialize, BorshDeserialize)]
+/// struct StateV1 {
+///     count: u32,
+/// }
+///
+/// impl VersionedState for StateV1 {
+///     const VERSION: u32 = 1;
+/// }
+///
+/// #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+/// struct State {
+///     count: u64,
+///     owner: String,
+/// }
+///
+/// fn migrate_from_v1(bytes: &[u8]) -> State {
+///     let old = StateV1::try_from_slice(bytes).unwrap();
+///     State { count: old.count as u64, owner: "alice".to_string() }
+/// }
+///
+/// impl VersionedState for State {
+///     const VERSION: u32 = 2;
+///     const MIGRATIONS: &'static [Migration<Self>] = &[Migration {
+///         from_version: 1,
+///         migrate: migrate_from_v1,
+///     }];
+/// }
+///
+/// // State written by the previous version of the contract.
+/// state::write(&StateV1 { count: 3 });
+///
+/// let state: State = state::read().unwrap();
+/// assert_eq!(state, State { count: 3, owner: "alice".to_string() });
+/// ```
+pub trait VersionedState: BorshSerialize + BorshDeserialize + 'static {
+    /// Version of the current state layout.
+    const VERSION: u32;
+
+    /// Migrations from older state layouts.
+    const MIGRATIONS: &'static [Migration<Self>] = &[];
+}
+
+/// Decodes the contract state, which was written with the layout of `version`. Returns `true` in
+/// the second value if the state was migrated from an older version.
+///
+/// Panics if there is no migration registered for `version`.
+fn decode<T: VersionedState>(version: u32, bytes: &[u8]) -> (T, bool) {
+    if version == T::VERSION {
+        return (
+            T::try_from_slice(bytes).unwrap_or_else(|_| env::abort()),
+            false,
+        );
+    }
+    match T::MIGRATIONS.iter().find(|m| m.from_version == version) {
+        Some(m) => ((m.migrate)(bytes), true),
+        None => env::panic_str("unknown contract state version"),
+    }
+}
+
+fn version() -> Option<u32> {
+    let mut buf = [0u8; 4];
+    match env::storage_read(VERSION_KEY, &mut buf)? {
+        4 => Some(u32::from_le_bytes(buf)),
+        _ => env::abort(),
+    }
+}
+
+fn read_migrated<T: VersionedState>() -> Option<(T, bool)> {
+    let bytes = utils::alloc_storage_read(env::STATE_KEY)?;
+    let version = version().unwrap_or(0);
+    Some(decode(version, &bytes))
+}
+
+/// Reads the contract state, migrating it if it was written by an older version. The migrated
+/// state is not written back to storage. Returns `None` if no state exists.
+///
+/// Panics if the state was written by a version that has no registered migration.
+///
+/// ```should_panic
+/// use nesdie_store::state::{self, VersionedState};
+///
+/// #[derive(borsh::BorshSerialize, borsh::BorshDeserialize)]
+/// struct StateV1(u8);
+/// impl VersionedState for StateV1 {
+///     const VERSION: u32 = 1;
+/// }
+///
+/// #[derive(borsh::BorshSerialize, borsh::BorshDeserialize)]
+/// struct State(u8);
+/// impl VersionedState for State {
+///     const VERSION: u32 = 2;
+/// }
+///
+/// state::write(&StateV1(0));
+///
+/// // No migration is registered from version 1.
+/// state::read::<State>();
+/// ```
+pub fn read<T: VersionedState>() -> Option<T> {
+    read_migrated().map(|(state, _)| state)
+}
+
+/// Writes the contract state along with the version tag of its layout.
+pub fn write<T: VersionedState>(state: &T) {
+    let bytes = state.try_to_vec().unwrap_or_else(|_| env::abort());
+    env::state_write_raw(&bytes);
+    env::storage_write(VERSION_KEY, &T::VERSION.to_le_bytes());
+}
+
+/// Contract state which is only read, and migrated, when it is first accessed. Changes are written
+/// back to storage with [`LazyState::flush`].
+///
+/// # Example
+/// ```
+/// use nesdie_store::state::{self, LazyState, VersionedState};
+///
+/// # #[derive(borsh::BorshSerialize, borsh::BorshDeserialize)]
+/// # struct State { count: u64 }
+/// impl VersionedState for State {
+///     const VERSION: u32 = 1;
+/// }
+///
+/// state::write(&State { count: 0 });
+///
+/// let mut lazy = LazyState::<State>::new();
+/// lazy.get_mut().count += 1;
+/// lazy.flush();
+///
+/// assert_eq!(state::read::<State>().unwrap().count, 1);
+/// ```
+pub struct LazyState<T> {
+    value: Option<T>,
+    modified: bool,
+}
+
+impl<T> Default for LazyState<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> LazyState<T> {
+    /// Creates a handle to the contract state without reading it from storage.
+    pub const fn new() -> Self {
+        Self {
+            value: None,
+            modified: false,
+        }
+    }
+
+    /// Replaces the contract state, without reading the existing state from storage.
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        self.modified = true;
+    }
+}
+
+impl<T: VersionedState> LazyState<T> {
+    fn load(&mut self) -> &mut T {
+        if self.value.is_none() {
+            let (state, migrated) = read_migrated().unwrap_or_else(|| env::abort());
+            self.value = Some(state);
+            // Migrated state is written back in the current layout.
+            self.modified |= migrated;
+        }
+        match self.value.as_mut() {
+            Some(value) => value,
+            None => env::abort(),
+        }
+    }
+
+    /// Returns a reference to the contract state, reading it from storage if it has not been
+    /// loaded yet.
+    ///
+    /// Aborts if no state exists, and panics if the state was written by a version that has no
+    /// registered migration.
+    pub fn get(&mut self) -> &T {
+        self.load()
+    }
+
+    /// Returns a mutable reference to the contract state, reading it from storage if it has not
+    /// been loaded yet. The state will be written on [`LazyState::flush`].
+    ///
+    /// Aborts if no state exists, and panics if the state was written by a version that has no
+    /// registered migration.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.modified = true;
+        self.load()
+    }
+
+    /// Writes the contract state to storage if it was modified or migrated since it was loaded.
+    pub fn flush(&mut self) {
+        if let (Some(value), true) = (&self.value, self.modified) {
+            write(value);
+            self.modified = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nesdie::mock::VmContextBuilder;
+    use nesdie::{testing_env, upgrade};
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    struct StateV1 {
+        count: u32,
+    }
+
+    impl VersionedState for StateV1 {
+        const VERSION: u32 = 1;
+    }
+
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    struct State {
+        count: u64,
+        flag: bool,
+    }
+
+    fn migrate_from_v1(bytes: &[u8]) -> State {
+        let old = StateV1::try_from_slice(bytes).unwrap();
+        State {
+            count: old.count as u64,
+            flag: true,
+        }
+    }
+
+    impl VersionedState for State {
+        const VERSION: u32 = 2;
+        const MIGRATIONS: &'static [Migration<Self>] = &[Migration {
+            from_version: 1,
+            migrate: migrate_from_v1,
+        }];
+    }
+
+    /// Upgrades the code to version 2, with a migration which doesn't rewrite the state.
+    fn upgrade_code() {
+        testing_env!(VmContextBuilder::new()
+            .current_account_id("alice".into())
+            .predecessor_account_id("alice".into())
+            .build());
+        write(&StateV1 { count: 3 });
+        upgrade::migrate(2, |_| ());
+        assert_eq!(env::state_version(), Some(2));
+    }
+
+    #[test]
+    fn read_after_code_upgrade() {
+        upgrade_code();
+        assert_eq!(
+            read::<State>(),
+            Some(State {
+                count: 3,
+                flag: true
+            })
+        );
+    }
+
+    #[test]
+    fn lazy_state_after_code_upgrade() {
+        upgrade_code();
+        let mut lazy = LazyState::<State>::new();
+        lazy.get_mut().count += 1;
+        lazy.flush();
+        assert_eq!(version(), Some(2));
+        assert_eq!(
+            read::<State>(),
+            Some(State {
+                count: 4,
+                flag: true
+            })
+        );
+    }
+}
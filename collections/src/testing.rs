@@ -0,0 +1,359 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use nesdie::mock::{set_mocked_blockchain, Gen, MockedBlockchain, StorageChange, StorageDiff};
+
+use crate::key::ToKey;
+use crate::lib::{BTreeMap, Box, Debug, String, Vec};
+use crate::KvStore;
+
+/// Change of a single entry of a collection, decoded from a [`StorageDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<K, V> {
+    /// Entry was added.
+    Added {
+        /// Key of the entry.
+        key: K,
+        /// Value of the entry.
+        value: V,
+    },
+    /// Value of the entry was changed.
+    Modified {
+        /// Key of the entry.
+        key: K,
+        /// Value in the snapshot.
+        old: V,
+        /// Current value.
+        new: V,
+    },
+    /// Entry was removed.
+    Removed {
+        /// Key of the entry.
+        key: K,
+        /// Value in the snapshot.
+        value: V,
+    },
+}
+
+impl<K, V> Change<K, V> {
+    /// Decodes a change of a storage key, returning `None` if the key or values can't be decoded.
+    pub(crate) fn decode(
+        key: &[u8],
+        change: &StorageChange,
+        decode_key: impl Fn(&[u8]) -> Option<K>,
+        decode_value: impl Fn(&[u8]) -> Option<V>,
+    ) -> Option<Self> {
+        let key = decode_key(key)?;
+        Some(match change {
+            StorageChange::Added(value) => Change::Added {
+                key,
+                value: decode_value(value)?,
+            },
+            StorageChange::Modified { old, new } => Change::Modified {
+                key,
+                old: decode_value(old)?,
+                new: decode_value(new)?,
+            },
+            StorageChange::Removed(value) => Change::Removed {
+                key,
+                value: decode_value(value)?,
+            },
+        })
+    }
+}
+
+/// Names the collections of a contract by their storage prefixes, to show which collections
+/// changed in a [`StorageDiff`]. Keys are matched to the collection with the longest matching
+/// prefix.
+///
+/// Only collections which store keys under their prefix can be matched, which excludes
+/// [`KvStore`](crate::KvStore)s with a hashing [`ToKey`](crate::key::ToKey) implementation.
+///
+/// # Example
+/// ```
+/// use nesdie::mock::with_mocked_blockchain;
+/// use nesdie_store::testing::StorageLayout;
+/// use nesdie_store::{AccessControl, KvStore};
+///
+/// let access = AccessControl::new(b"a".to_vec().into_boxed_slice());
+/// let mut balances: KvStore<u8, u64> = KvStore::new(b"b".to_vec().into_boxed_slice());
+/// let layout = StorageLayout::new()
+///     .collection("access", b"a")
+///     .collection("balances", b"b");
+///
+/// let snapshot = with_mocked_blockchain(|b| b.snapshot());
+/// access.init("alice");
+/// balances.insert(&1, &100);
+///
+/// let diff = with_mocked_blockchain(|b| b.diff(&snapshot));
+/// assert_eq!(layout.changed(&diff), vec!["access", "balances"]);
+/// assert_eq!(layout.describe(&diff), "+ access o\n+ balances \\x01\n");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StorageLayout {
+    collections: Vec<(&'static str, Box<[u8]>)>,
+}
+
+impl StorageLayout {
+    /// Creates an empty layout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a collection named `name` which stores its data under `prefix`.
+    pub fn collection(mut self, name: &'static str, prefix: &[u8]) -> Self {
+        self.collections.push((name, prefix.into()));
+        self
+    }
+
+    fn find(&self, key: &[u8]) -> Option<&(&'static str, Box<[u8]>)> {
+        self.collections
+            .iter()
+            .filter(|(_, prefix)| key.starts_with(prefix))
+            .max_by_key(|(_, prefix)| prefix.len())
+    }
+
+    /// Returns the name of the collection which stores `key`.
+    pub fn collection_of(&self, key: &[u8]) -> Option<&'static str> {
+        self.find(key).map(|(name, _)| *name)
+    }
+
+    /// Returns the names of the collections with changes in `diff`, in the order they were added
+    /// to the layout.
+    pub fn changed(&self, diff: &StorageDiff) -> Vec<&'static str> {
+        let changed: Vec<_> = diff
+            .iter()
+            .filter_map(|(key, _)| self.collection_of(key))
+            .collect();
+        self.collections
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| changed.contains(name))
+            .collect()
+    }
+
+    /// Describes the changes in `diff` with one line per key, sorted by key. Each line has the
+    /// kind of change (`+`, `~` or `-`), the name of the collection or `?` if the key is not in
+    /// any collection, and the key without the prefix of the collection.
+    pub fn describe(&self, diff: &StorageDiff) -> String {
+        let mut description = String::new();
+        for (key, change) in diff.iter() {
+            let kind = match change {
+                StorageChange::Added(_) => '+',
+                StorageChange::Modified { .. } => '~',
+                StorageChange::Removed(_) => '-',
+            };
+            let (name, key) = match self.find(key) {
+                Some((name, prefix)) => (*name, &key[prefix.len()..]),
+                None => ("?", key),
+            };
+            description.push(kind);
+            description.push(' ');
+            description.push_str(name);
+            description.push(' ');
+            description.extend(key.iter().flat_map(|b| b.escape_ascii()).map(char::from));
+            description.push('\n');
+        }
+        description
+    }
+}
+
+/// Collection or reference model that operations of type `Op` can be applied to, to compare
+/// them with [`Differential`].
+pub trait Model<Op> {
+    /// Result of an operation.
+    type Output: PartialEq + Debug;
+
+    /// Applies `op` and returns its result.
+    fn apply(&mut self, op: &Op) -> Self::Output;
+}
+
+/// Operation on a map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapOp<K, V> {
+    /// Inserts the value under the key.
+    Insert(K, V),
+    /// Gets the value of the key.
+    Get(K),
+    /// Removes the key.
+    Remove(K),
+    /// Checks whether the key is present.
+    ContainsKey(K),
+}
+
+impl<K, V> MapOp<K, V> {
+    /// Generates an operation of a random kind, with keys and values generated by `key` and
+    /// `value`. Generating keys from a small set makes operations on existing keys likely.
+    pub fn random<KF, VF>(rng: &mut Gen, mut key: KF, mut value: VF) -> Self
+    where
+        KF: FnMut(&mut Gen) -> K,
+        VF: FnMut(&mut Gen) -> V,
+    {
+        match rng.range(0..4) {
+            0 => MapOp::Insert(key(rng), value(rng)),
+            1 => MapOp::Get(key(rng)),
+            2 => MapOp::Remove(key(rng)),
+            _ => MapOp::ContainsKey(key(rng)),
+        }
+    }
+}
+
+/// Result of a [`MapOp`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapOutput<V> {
+    /// Whether the key was present before an insert.
+    Inserted(bool),
+    /// Value returned by a get or remove.
+    Value(Option<V>),
+    /// Whether the key is present.
+    Contains(bool),
+}
+
+impl<K, V> Model<MapOp<K, V>> for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + PartialEq + Debug,
+{
+    type Output = MapOutput<V>;
+
+    fn apply(&mut self, op: &MapOp<K, V>) -> Self::Output {
+        match op {
+            MapOp::Insert(key, value) => {
+                MapOutput::Inserted(self.insert(key.clone(), value.clone()).is_some())
+            }
+            MapOp::Get(key) => MapOutput::Value(self.get(key).cloned()),
+            MapOp::Remove(key) => MapOutput::Value(self.remove(key)),
+            MapOp::ContainsKey(key) => MapOutput::Contains(self.contains_key(key)),
+        }
+    }
+}
+
+impl<K, V, H> Model<MapOp<K, V>> for KvStore<K, V, H>
+where
+    K: BorshSerialize,
+    V: BorshSerialize + BorshDeserialize + PartialEq + Debug,
+    H: ToKey,
+{
+    type Output = MapOutput<V>;
+
+    fn apply(&mut self, op: &MapOp<K, V>) -> Self::Output {
+        match op {
+            MapOp::Insert(key, value) => MapOutput::Inserted(self.insert(key, value)),
+            MapOp::Get(key) => MapOutput::Value(self.get(key)),
+            MapOp::Remove(key) => MapOutput::Value(self.remove(key)),
+            MapOp::ContainsKey(key) => MapOutput::Contains(self.contains_key(key)),
+        }
+    }
+}
+
+/// First operation for which a collection and its reference model returned different results
+/// in [`Differential::check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<Op, O> {
+    /// Seed of the run which found the mismatch.
+    pub seed: u64,
+    /// Operations of the run, ending with the mismatching operation.
+    pub ops: Vec<Op>,
+    /// Result of the collection.
+    pub collection: O,
+    /// Result of the reference model.
+    pub model: O,
+}
+
+/// Differential tests of a collection against a reference model. Random operations are applied
+/// to both and every result is compared. Each run starts with a new [`MockedBlockchain`], so
+/// storage is empty and gas isn't carried over.
+///
+/// # Example
+/// ```
+/// use nesdie_store::key::Sha256;
+/// use nesdie_store::testing::{Differential, MapOp};
+/// use nesdie_store::KvStore;
+/// use std::collections::BTreeMap;
+///
+/// let result = Differential::new().runs(10).check(
+///     || KvStore::<u8, u32, Sha256>::with_hasher(b"m".to_vec().into_boxed_slice()),
+///     BTreeMap::new,
+///     |rng| MapOp::random(rng, |rng| rng.range(0..8) as u8, |rng| rng.u64() as u32),
+/// );
+/// assert_eq!(result, Ok(()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Differential {
+    runs: u64,
+    ops: usize,
+    seed: u64,
+}
+
+impl Default for Differential {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Differential {
+    /// Creates a test of 50 runs with 100 operations each.
+    pub fn new() -> Self {
+        Self {
+            runs: 50,
+            ops: 100,
+            seed: 0,
+        }
+    }
+
+    /// Sets the number of runs.
+    pub fn runs(mut self, runs: u64) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Sets the number of operations of each run.
+    pub fn ops(mut self, ops: usize) -> Self {
+        self.ops = ops;
+        self
+    }
+
+    /// Sets the seed of the first run. Each following run increments the seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Runs operations generated by `generate` on the collections created by `collection` and
+    /// `model`, returning the first mismatch.
+    pub fn check<Op, C, M, CF, MF, G>(
+        &self,
+        mut collection: CF,
+        mut model: MF,
+        mut generate: G,
+    ) -> Result<(), Mismatch<Op, C::Output>>
+    where
+        C: Model<Op>,
+        M: Model<Op, Output = C::Output>,
+        CF: FnMut() -> C,
+        MF: FnMut() -> M,
+        G: FnMut(&mut Gen) -> Op,
+    {
+        for run in 0..self.runs {
+            let seed = self.seed.wrapping_add(run);
+            let mut rng = Gen::new(seed);
+            set_mocked_blockchain(MockedBlockchain::default());
+            let mut collection = collection();
+            let mut model = model();
+            let mut ops = Vec::with_capacity(self.ops);
+            for _ in 0..self.ops {
+                let op = generate(&mut rng);
+                let collection_output = collection.apply(&op);
+                let model_output = model.apply(&op);
+                ops.push(op);
+                if collection_output != model_output {
+                    return Err(Mismatch {
+                        seed,
+                        ops,
+                        collection: collection_output,
+                        model: model_output,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
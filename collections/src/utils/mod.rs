@@ -2,8 +2,8 @@ use crate::lib::Vec;
 use core::convert::TryInto;
 use nesdie::{env, sys};
 
-const ATOMIC_OP_REGISTER: u64 = core::u64::MAX - 1;
-const EVICTED_REGISTER: u64 = core::u64::MAX - 2;
+const ATOMIC_OP_REGISTER: u64 = u64::MAX - 1;
+const EVICTED_REGISTER: u64 = u64::MAX - 2;
 
 /// Reads the value stored under the given key.
 pub(crate) fn alloc_storage_read(key: &[u8]) -> Option<Vec<u8>> {
@@ -43,7 +43,7 @@ pub fn read_register_alloc(register_id: u64) -> Option<Vec<u8>> {
 /// Returns the size of the register. If register is not used returns `None`.
 fn register_len(register_id: u64) -> Option<u64> {
     let len = unsafe { sys::register_len(register_id) };
-    if len == core::u64::MAX {
+    if len == u64::MAX {
         None
     } else {
         Some(len)
@@ -53,7 +53,7 @@ fn register_len(register_id: u64) -> Option<u64> {
 /// Removes the value stored under the given key.
 /// If key-value existed returns `true`, otherwise `false`.
 pub(crate) fn storage_remove_alloc(key: &[u8]) -> Option<Vec<u8>> {
-    let removed = env::storage_remove(&key);
+    let removed = env::storage_remove(key);
     if removed {
         read_register_alloc(EVICTED_REGISTER)
     } else {
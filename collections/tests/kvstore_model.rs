@@ -0,0 +1,64 @@
+//! Differential tests of `KvStore` against a `BTreeMap`, for each `ToKey` implementation.
+
+use nesdie::mock::Gen;
+use nesdie_store::key::{Identity, Keccak256, Sha256, ToKey};
+use nesdie_store::testing::{Differential, MapOp};
+use nesdie_store::KvStore;
+use std::collections::BTreeMap;
+
+fn check_hasher<H: ToKey>() {
+    // Small key space, so most operations are on existing keys.
+    let result = Differential::new().check(
+        || KvStore::<u8, String, H>::with_hasher(b"m".to_vec().into_boxed_slice()),
+        BTreeMap::new,
+        |rng| MapOp::random(rng, |rng| rng.range(0..16) as u8, random_string),
+    );
+    assert_eq!(result, Ok(()));
+}
+
+fn random_string(rng: &mut Gen) -> String {
+    let len = rng.index(8);
+    (0..len)
+        .map(|_| (b'a' + rng.range(0..26) as u8) as char)
+        .collect()
+}
+
+#[test]
+fn identity_matches_model() {
+    check_hasher::<Identity>();
+}
+
+#[test]
+fn sha256_matches_model() {
+    check_hasher::<Sha256>();
+}
+
+#[test]
+fn keccak256_matches_model() {
+    check_hasher::<Keccak256>();
+}
+
+#[test]
+fn string_keys_match_model() {
+    // Keys which are prefixes of each other must not collide.
+    let result = Differential::new().check(
+        || KvStore::<String, u64>::new(b"m".to_vec().into_boxed_slice()),
+        BTreeMap::new,
+        |rng| MapOp::random(rng, random_string, |rng| rng.u64()),
+    );
+    assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn detects_mismatch() {
+    // Model which doesn't start empty differs on the first operation.
+    let mut model = BTreeMap::new();
+    model.insert(0u8, 0u64);
+    let result = Differential::new().runs(1).check(
+        || KvStore::<u8, u64>::new(b"m".to_vec().into_boxed_slice()),
+        || model.clone(),
+        |_| MapOp::ContainsKey(0),
+    );
+    let mismatch = result.unwrap_err();
+    assert_eq!(mismatch.ops, vec![MapOp::ContainsKey(0)]);
+}
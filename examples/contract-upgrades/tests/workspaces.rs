@@ -4,6 +4,7 @@ async fn test_upgrade() -> anyhow::Result<()> {
     let contract = worker
         .dev_deploy(&workspaces::compile_project("./upgrade-a").await?)
         .await?;
+    let upgrade_b = workspaces::compile_project("./upgrade-b").await?;
 
     assert!(contract
         .call("some_new_function")
@@ -11,9 +12,24 @@ async fn test_upgrade() -> anyhow::Result<()> {
         .await?
         .is_failure());
 
+    // Accounts other than the contract itself cannot upgrade.
+    let other = worker.dev_create_account().await?;
+    assert!(other
+        .call(contract.id(), "upgrade")
+        .args(upgrade_b.clone())
+        .max_gas()
+        .transact()
+        .await?
+        .is_failure());
+    assert!(contract
+        .call("some_new_function")
+        .transact()
+        .await?
+        .is_failure());
+
     let res = contract
         .call("upgrade")
-        .args(workspaces::compile_project("./upgrade-b").await?)
+        .args(upgrade_b.clone())
         .max_gas()
         .transact()
         .await?
@@ -25,5 +41,44 @@ async fn test_upgrade() -> anyhow::Result<()> {
     assert_eq!(res.logs()[0], "can call some new function now!");
     assert!(res.is_success());
 
+    // Redeploying the same version does not run the migration again.
+    let res = contract
+        .call("upgrade")
+        .args(upgrade_b)
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(res.logs().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_downgrade_refused() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let upgrade_a = workspaces::compile_project("./upgrade-a").await?;
+    let contract = worker.dev_deploy(&upgrade_a).await?;
+
+    contract
+        .call("upgrade")
+        .args(workspaces::compile_project("./upgrade-b").await?)
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    // Migration to an older state version fails, which reverts the deployment.
+    assert!(contract
+        .call("upgrade")
+        .args(upgrade_a)
+        .max_gas()
+        .transact()
+        .await?
+        .is_failure());
+
+    let res = contract.call("some_new_function").transact().await?;
+    assert!(res.is_success());
+
     Ok(())
 }
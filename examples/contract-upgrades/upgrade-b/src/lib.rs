@@ -1,14 +1,17 @@
 #![cfg_attr(target_arch = "wasm32", no_std)]
 
-const MIGRATE_FUNCTION_NAME: &str = "migrate";
-/// Gas for calling migration call.
-const GAS_FOR_MIGRATE_CALL: u64 = 5_000_000_000_000;
+/// Version of the contract state for this code.
+const CODE_VERSION: u32 = 2;
+/// Gas kept for the upgrade call after scheduling the migration.
+const GAS_FOR_UPGRADE_CALL: u64 = 5_000_000_000_000;
 
-use nesdie::{env, sys};
+use nesdie::{env, upgrade};
 
 #[no_mangle]
 pub fn migrate() {
-    env::log_str("performing arbitrary migration logic");
+    upgrade::migrate(CODE_VERSION, |_| {
+        env::log_str("performing arbitrary migration logic");
+    });
 }
 
 #[no_mangle]
@@ -18,26 +21,6 @@ pub fn some_new_function() {
 
 #[no_mangle]
 pub fn upgrade() {
-    //* Might want to assert a contract owner, otherwise anyone can upgrade
-
-    let current_account_id = env::current_account_id();
-
-    // Put input bytes into register 1. This should be the wasm contract
-    unsafe { sys::input(1) };
-
-    let promise_id = env::promise_batch_create(current_account_id.as_str());
-    unsafe { sys::promise_batch_action_deploy_contract(promise_id.0, u64::MAX as _, 1) };
-
-    let attached_gas = env::prepaid_gas() - env::used_gas() - GAS_FOR_MIGRATE_CALL;
-    unsafe {
-        sys::promise_batch_action_function_call(
-            promise_id.0,
-            MIGRATE_FUNCTION_NAME.len() as _,
-            MIGRATE_FUNCTION_NAME.as_ptr() as _,
-            0 as _,
-            0 as _,
-            0 as _,
-            attached_gas,
-        )
-    };
+    // Only the account's own keys can upgrade the contract.
+    upgrade::deploy_from_input(env::current_account_id().as_str(), GAS_FOR_UPGRADE_CALL);
 }
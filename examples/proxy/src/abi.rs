@@ -0,0 +1,10 @@
+nesdie::abi! {
+    /// This proxies passed call.
+    /// Checks that predecessor is suffix of the given account.
+    call fn call(raw("<gas:u64><amount:u128><receiver_len:u32><receiver_id:bytes><method_name_len:u32><method_name:bytes><args_len:u32><args:bytes>"));
+    /// Transfers given amount of $NEAR to given account.
+    call fn transfer(raw("<amount:u128><receiver_id:bytes>"));
+    /// This allows to update the contract on this account.
+    /// Checks that predecessor is suffix of the given account.
+    call fn update(raw("<code:bytes>"));
+}
@@ -5,7 +5,10 @@ extern crate alloc;
 use alloc::vec;
 
 #[cfg(target_arch = "wasm32")]
-use nesdie::sys;
+use nesdie::{env, sys};
+
+/// Description of the exported methods, which is not compiled into the contract.
+mod abi;
 
 /// Check that predecessor of given account if suffix of given account.
 fn assert_predecessor() {
@@ -18,7 +21,7 @@ fn assert_predecessor() {
         predecessor_account[0] = b'.';
         sys::read_register(1, predecessor_account[1..].as_ptr() as *const u64 as u64);
         if !current_account.ends_with(&predecessor_account) {
-            sys::panic();
+            env::abort();
         }
     }
 }
@@ -0,0 +1,8 @@
+nesdie::abi! {
+    /// Initializes the token contract with the total supply given to the owner.
+    call fn init(raw("<owner_hash:32><total_supply:u256>"));
+    /// Transfer the amount from the `sha256(predecessor_account_id)` to the new receiver address.
+    call fn transfer(raw("<receiver_hash:32><amount:u256>"));
+    /// Returns the balance of the given address.
+    view fn get_balance(raw("<account_hash:32>")) -> raw("<balance:u256>");
+}
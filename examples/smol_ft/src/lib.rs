@@ -4,7 +4,10 @@
 //* https://github.com/near/core-contracts/pull/88
 
 #[cfg(target_arch = "wasm32")]
-use nesdie::sys;
+use nesdie::{env, sys};
+
+/// Description of the exported methods, which is not compiled into the contract.
+mod abi;
 
 const SUPPLY_KEY: &[u8] = b"S";
 const LEN: u64 = 32;
@@ -23,7 +26,7 @@ unsafe fn add(a: &U256, b: &U256, res: &mut U256) {
     }
     if old_overflow {
         // Overflow
-        sys::panic();
+        env::abort();
     }
 }
 
@@ -37,7 +40,7 @@ unsafe fn sub(a: &U256, b: &U256, res: &mut U256) {
     }
     if old_underflow {
         // Underflow
-        sys::panic();
+        env::abort();
     }
 }
 
@@ -48,7 +51,7 @@ unsafe fn sub(a: &U256, b: &U256, res: &mut U256) {
 #[no_mangle]
 pub unsafe fn init() {
     if sys::storage_has_key(SUPPLY_KEY.len() as _, SUPPLY_KEY.as_ptr() as _) == 1 {
-        sys::panic();
+        env::abort();
     }
     let buf = read_input();
     // SUPPLY_KEY
@@ -67,10 +70,10 @@ unsafe fn read_input() -> [u64; LEN_U64_USIZE * 2] {
     sys::input(0);
     let input_len = sys::register_len(0);
     if input_len != LEN * 2 {
-        sys::panic();
+        env::abort();
     }
-    let buf = [0u64; LEN_U64_USIZE * 2];
-    sys::read_register(0, buf.as_ptr() as _);
+    let mut buf = [0u64; LEN_U64_USIZE * 2];
+    sys::read_register(0, buf.as_mut_ptr() as _);
     buf
 }
 
@@ -88,11 +91,11 @@ pub unsafe fn transfer() {
     // Owner's balance to register 1
     if sys::storage_read(u64::MAX, 0, 1) == 0 {
         // No balance
-        sys::panic();
+        env::abort();
     }
 
-    let owner_balance = [0u64; LEN_U64_USIZE];
-    sys::read_register(1, owner_balance.as_ptr() as _);
+    let mut owner_balance = [0u64; LEN_U64_USIZE];
+    sys::read_register(1, owner_balance.as_mut_ptr() as _);
 
     let transfer_balance = &buf[LEN_U64_USIZE..LEN_U64_USIZE * 2];
     let mut new_balance = [0u64; LEN_U64_USIZE];
@@ -101,10 +104,10 @@ pub unsafe fn transfer() {
     // Write new owner balance
     sys::storage_write(u64::MAX, 0, LEN, new_balance.as_ptr() as _, 1);
 
-    let receiver_balance = [0u64; LEN_U64_USIZE];
+    let mut receiver_balance = [0u64; LEN_U64_USIZE];
     // Reading and filling receiver_balance.
     if sys::storage_read(LEN, buf.as_ptr() as u64, 1) == 1 {
-        sys::read_register(1, receiver_balance.as_ptr() as _);
+        sys::read_register(1, receiver_balance.as_mut_ptr() as _);
     }
 
     // Reusing `new_balance`, since it overwrites all bytes.
@@ -122,7 +125,7 @@ pub unsafe fn get_balance() {
     sys::input(0);
     let input_len = sys::register_len(0);
     if input_len != LEN {
-        sys::panic();
+        env::abort();
     }
 
     // Reading receiver_balance and returning it, or returning 0.
@@ -0,0 +1,51 @@
+//! Feeds arbitrary input to the entry points of the `smol_ft` example, run in-process by the
+//! interpreter of the mock. The example has to be built with `examples/smol_ft/build.sh` first.
+//!
+//! Aborts through the `unreachable` instruction, contract panics and exceeding the prepaid gas are
+//! expected for invalid input. Any other error, such as an out of bounds memory access, fails the
+//! target.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nesdie::mock::{
+    with_mocked_blockchain, HostError, VMLogicError, VmContextBuilder, WasmContract, WasmError,
+};
+
+const METHODS: [&str; 3] = ["init", "transfer", "get_balance"];
+
+thread_local! {
+    static CONTRACT: WasmContract = {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/smol_ft/res/smol_ft.wasm");
+        let code = std::fs::read(path).expect("smol_ft example has to be built first");
+        WasmContract::new(&code).unwrap()
+    };
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the method, the rest is the input of the call.
+    let (method, input) = match data.split_first() {
+        Some((method, input)) => (METHODS[*method as usize % METHODS.len()], input),
+        None => return,
+    };
+    with_mocked_blockchain(|b| b.storage_mut().clear());
+    CONTRACT.with(|contract| {
+        // Initialize from the input, so transfers can have a balance to move.
+        contract.call(VmContextBuilder::new().input(input.to_vec()).build(), "init");
+        let outcome = contract.call(
+            VmContextBuilder::new()
+                .predecessor_account_id("alice".into())
+                .input(input.to_vec())
+                .build(),
+            method,
+        );
+        match outcome.error {
+            None
+            | Some(WasmError::Host(VMLogicError::HostError(
+                HostError::GuestPanic { .. } | HostError::GasExceeded,
+            ))) => {}
+            // `env::abort` traps with `unreachable`.
+            Some(WasmError::Trap(trap)) if trap.contains("`unreachable`") => {}
+            Some(error) => panic!("{} failed: {:?}", method, error),
+        }
+    });
+});
@@ -0,0 +1,170 @@
+use std::fmt::Write;
+
+/// Description of the methods exported by a contract, declared with [`abi!`](crate::abi!).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Abi {
+    /// Exported methods, in declaration order.
+    pub methods: Vec<Method>,
+}
+
+/// Description of a single exported method.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Method {
+    /// Name of the exported function.
+    pub name: &'static str,
+    /// Lines of the doc comment of the method.
+    pub docs: Vec<&'static str>,
+    /// Whether the method can change state.
+    pub mutability: Mutability,
+    /// Whether the method accepts an attached deposit.
+    pub payable: bool,
+    /// Encoding of the input.
+    pub input: Encoding,
+    /// Encoding of the returned value.
+    pub output: Encoding,
+}
+
+/// Whether a method can change state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mutability {
+    /// Method only reads state, and can be called without a transaction.
+    View,
+    /// Method can change state, and has to be called with a transaction.
+    Call,
+}
+
+/// Encoding of the input or output of a method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// No input is read or no value is returned.
+    None,
+    /// Bytes with a custom layout, described as `<name:type>` fields in order.
+    Raw(&'static str),
+    /// Borsh encoding of the given type.
+    Borsh(&'static str),
+    /// JSON encoding of the given type.
+    Json(&'static str),
+}
+
+impl Abi {
+    /// Returns the method named `name`.
+    pub fn method(&self, name: &str) -> Option<&Method> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    /// Formats the ABI as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n  \"methods\": [");
+        for (i, method) in self.methods.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let docs: Vec<_> = method
+                .docs
+                .iter()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect();
+            json.push_str("\n    {\n      \"name\": ");
+            push_str(&mut json, method.name);
+            json.push_str(",\n      \"doc\": ");
+            push_str(&mut json, &docs.join("\n"));
+            let mutability = match method.mutability {
+                Mutability::View => "view",
+                Mutability::Call => "call",
+            };
+            write!(
+                json,
+                ",\n      \"mutability\": \"{}\",\n      \"payable\": {}",
+                mutability, method.payable
+            )
+            .unwrap();
+            json.push_str(",\n      \"input\": ");
+            push_encoding(&mut json, &method.input);
+            json.push_str(",\n      \"output\": ");
+            push_encoding(&mut json, &method.output);
+            json.push_str("\n    }");
+        }
+        if !self.methods.is_empty() {
+            json.push_str("\n  ");
+        }
+        json.push_str("]\n}\n");
+        json
+    }
+}
+
+fn push_encoding(json: &mut String, encoding: &Encoding) {
+    let (name, field, value) = match encoding {
+        Encoding::None => ("none", "", ""),
+        Encoding::Raw(layout) => ("raw", "layout", *layout),
+        Encoding::Borsh(ty) => ("borsh", "type", *ty),
+        Encoding::Json(ty) => ("json", "type", *ty),
+    };
+    write!(json, "{{ \"encoding\": \"{}\"", name).unwrap();
+    if !field.is_empty() {
+        write!(json, ", \"{}\": ", field).unwrap();
+        push_str(json, value);
+    }
+    json.push_str(" }");
+}
+
+fn push_str(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    crate::abi! {
+        /// Returns the "balance".
+        ///
+        /// Zero if the account is unknown.
+        view fn balance(borsh(Vec<u8>)) -> json(u128);
+        payable fn deposit();
+        call fn transfer(raw("<amount:u128>"));
+    }
+
+    #[test]
+    fn abi_json() {
+        assert_eq!(
+            abi().to_json(),
+            r#"{
+  "methods": [
+    {
+      "name": "balance",
+      "doc": "Returns the \"balance\".\n\nZero if the account is unknown.",
+      "mutability": "view",
+      "payable": false,
+      "input": { "encoding": "borsh", "type": "Vec<u8>" },
+      "output": { "encoding": "json", "type": "u128" }
+    },
+    {
+      "name": "deposit",
+      "doc": "",
+      "mutability": "call",
+      "payable": true,
+      "input": { "encoding": "none" },
+      "output": { "encoding": "none" }
+    },
+    {
+      "name": "transfer",
+      "doc": "",
+      "mutability": "call",
+      "payable": false,
+      "input": { "encoding": "raw", "layout": "<amount:u128>" },
+      "output": { "encoding": "none" }
+    }
+  ]
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::wasm32;
+
+#[cfg(all(
+    feature = "wee_alloc",
+    not(feature = "bump-alloc"),
+    not(feature = "std")
+))]
+mod trap {
+    use super::*;
+
+    /// Returns `ptr`, trapping with `unreachable` if the allocation failed.
+    ///
+    /// Returning a null pointer would call the alloc error handler, which panics with a formatted
+    /// message and can only be replaced on nightly with `#[alloc_error_handler]`. Trapping here
+    /// instead lets the compiler remove the call to the handler entirely.
+    #[inline]
+    fn non_null(ptr: *mut u8) -> *mut u8 {
+        if ptr.is_null() {
+            wasm32::unreachable()
+        }
+        ptr
+    }
+
+    /// Wrapper of an allocator which traps on allocation failure, see [`non_null`].
+    pub struct TrapOnOom<A>(pub A);
+
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for TrapOnOom<A> {
+        #[inline]
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            non_null(self.0.alloc(layout))
+        }
+
+        #[inline]
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            non_null(self.0.alloc_zeroed(layout))
+        }
+
+        #[inline]
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.0.dealloc(ptr, layout)
+        }
+
+        #[inline]
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            non_null(self.0.realloc(ptr, layout, new_size))
+        }
+    }
+}
+
+#[cfg(feature = "bump-alloc")]
+mod bump {
+    use super::*;
+
+    const PAGE_SIZE: usize = 64 * 1024;
+
+    extern "C" {
+        /// Start of the heap after the stack and static data, defined by the linker.
+        static __heap_base: u8;
+    }
+
+    /// Next free address of the heap, or `0` before the first allocation.
+    static mut NEXT: usize = 0;
+
+    /// Allocator which hands out memory from the end of the heap and never frees it.
+    ///
+    /// Contract executions are short-lived and the memory is discarded after each call, so
+    /// keeping track of freed memory is not worth the code size. Linear memory is grown when the
+    /// heap is exhausted, and running out of memory traps instead of returning a null pointer.
+    pub struct BumpAlloc;
+
+    unsafe impl GlobalAlloc for BumpAlloc {
+        #[inline]
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let next = match NEXT {
+                0 => core::ptr::addr_of!(__heap_base) as usize,
+                next => next,
+            };
+            // Alignment is always a power of two.
+            let start = match next.checked_add(layout.align() - 1) {
+                Some(start) => start & !(layout.align() - 1),
+                None => wasm32::unreachable(),
+            };
+            let end = match start.checked_add(layout.size()) {
+                Some(end) => end,
+                None => wasm32::unreachable(),
+            };
+            //* Contract memory is limited far below 4GiB, so the size in bytes can't overflow.
+            let memory_end = wasm32::memory_size(0) * PAGE_SIZE;
+            if end > memory_end {
+                let pages = (end - memory_end - 1) / PAGE_SIZE + 1;
+                if wasm32::memory_grow(0, pages) == usize::MAX {
+                    wasm32::unreachable()
+                }
+            }
+            NEXT = end;
+            start as *mut u8
+        }
+
+        #[inline]
+        unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+    }
+}
+
+#[cfg(feature = "bump-alloc")]
+pub use self::bump::BumpAlloc;
+#[cfg(all(
+    feature = "wee_alloc",
+    not(feature = "bump-alloc"),
+    not(feature = "std")
+))]
+pub use self::trap::TrapOnOom;
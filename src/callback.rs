@@ -0,0 +1,236 @@
+use crate::env::{self, PromiseResult};
+use crate::types::Vec;
+use crate::{AccountId, Balance};
+
+/// Maximum length in bytes of a promise result decoded through [`CallbackValue`]. Larger results
+/// can be read with [`env::promise_result`] into a caller provided buffer.
+pub const MAX_CALLBACK_VALUE_LEN: usize = 64;
+
+/// Aborts unless the predecessor of the call is the current account. Callbacks scheduled with
+/// [`env::promise_then`] should be guarded by this, so that they can't be called by other accounts.
+pub fn assert_private() {
+    if env::predecessor_account_id() != env::current_account_id() {
+        env::abort();
+    }
+}
+
+/// Value which can be decoded from the result data of a promise. Integers are decoded from their
+/// little endian bytes, which is how nesdie contracts return them through [`env::value_return`].
+pub trait CallbackValue: Sized {
+    /// Whether values of the type have encodings of different lengths, which codecs of
+    /// [`ext`](crate::ext) may prefix with their length.
+    const VARIABLE_LEN: bool = false;
+
+    /// Decodes the value from the result data, returning `None` if the data is invalid.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl CallbackValue for () {
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+impl CallbackValue for bool {
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_callback_value_int {
+    ($($ty:ty),*) => {
+        $(impl CallbackValue for $ty {
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                let mut buf = [0u8; core::mem::size_of::<$ty>()];
+                if bytes.len() != buf.len() {
+                    return None;
+                }
+                buf.copy_from_slice(bytes);
+                Some(<$ty>::from_le_bytes(buf))
+            }
+        })*
+    };
+}
+
+impl_callback_value_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<const N: usize> CallbackValue for [u8; N] {
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut buf = [0u8; N];
+        if bytes.len() != N {
+            return None;
+        }
+        buf.copy_from_slice(bytes);
+        Some(buf)
+    }
+}
+
+impl CallbackValue for AccountId {
+    const VARIABLE_LEN: bool = true;
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut account_id = AccountId::new();
+        account_id
+            .push_str(core::str::from_utf8(bytes).ok()?)
+            .ok()?;
+        Some(account_id)
+    }
+}
+
+/// Reads and decodes the result of the promise at `result_idx`. Returns `None` if the promise
+/// failed.
+///
+/// Aborts if there is no result at the index, or if the result data can't be decoded as `T`.
+pub fn result<T: CallbackValue>(result_idx: u64) -> Option<T> {
+    let mut buf = Vec::<u8, MAX_CALLBACK_VALUE_LEN>::new();
+    buf.resize(MAX_CALLBACK_VALUE_LEN, 0)
+        .unwrap_or_else(|_| env::abort());
+    match env::promise_result(result_idx, &mut buf) {
+        PromiseResult::Successful(len) => {
+            Some(T::decode(&buf[..len]).unwrap_or_else(|| env::abort()))
+        }
+        PromiseResult::Failed => None,
+        PromiseResult::NotReady => env::abort(),
+    }
+}
+
+/// Reads and decodes the result of the promise at `result_idx`. If the promise failed,
+/// `rollback` is called to restore the state changed before the promise was scheduled, and
+/// `None` is returned.
+///
+/// # Example
+/// ```
+/// use nesdie::callback;
+/// use nesdie::mock::{PromiseResult, VmContextBuilder};
+/// use nesdie::testing_env;
+///
+/// testing_env!(
+///     VmContextBuilder::new().build(),
+///     Default::default(),
+///     Default::default(),
+///     Default::default(),
+///     vec![PromiseResult::Failed],
+/// );
+///
+/// // Refund the sender if the transfer failed, as `ft_resolve_transfer` does.
+/// let mut refunded = 0;
+/// let unused: Option<u128> = callback::resolve_or_rollback(0, || refunded = 10);
+/// assert_eq!(unused, None);
+/// assert_eq!(refunded, 10);
+/// ```
+pub fn resolve_or_rollback<T, F>(result_idx: u64, rollback: F) -> Option<T>
+where
+    T: CallbackValue,
+    F: FnOnce(),
+{
+    let value = result(result_idx);
+    if value.is_none() {
+        rollback();
+    }
+    value
+}
+
+/// Amount of `amount` which was not used by the promise at `result_idx`, based on the unused
+/// amount returned by the promise as a little endian [`Balance`]. This is capped to `amount`, and
+/// if the promise failed the full `amount` is unused.
+///
+/// This is the pattern used to refund the sender of a transfer call in a resolve callback.
+pub fn unused_amount(result_idx: u64, amount: Balance) -> Balance {
+    match result::<Balance>(result_idx) {
+        Some(unused) => core::cmp::min(unused, amount),
+        None => amount,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{PromiseResult as VmPromiseResult, VmContextBuilder};
+    use crate::testing_env;
+
+    fn with_results(predecessor: &str, results: std::vec::Vec<VmPromiseResult>) {
+        testing_env!(
+            VmContextBuilder::new()
+                .current_account_id("alice".into())
+                .predecessor_account_id(predecessor.into())
+                .build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            results,
+        );
+    }
+
+    #[test]
+    fn private_callback() {
+        with_results("alice", vec![]);
+        assert_private();
+    }
+
+    #[test]
+    #[should_panic]
+    fn private_callback_rejects_other() {
+        with_results("bob", vec![]);
+        assert_private();
+    }
+
+    #[test]
+    fn typed_results() {
+        with_results(
+            "alice",
+            vec![
+                VmPromiseResult::Successful(7u64.to_le_bytes().to_vec()),
+                VmPromiseResult::Failed,
+                VmPromiseResult::Successful(b"bob".to_vec()),
+                VmPromiseResult::Successful(vec![]),
+            ],
+        );
+        assert_eq!(env::promise_results_count(), 4);
+        assert_eq!(result::<u64>(0), Some(7));
+        assert_eq!(result::<u64>(1), None);
+        assert_eq!(result::<AccountId>(2).unwrap(), "bob");
+        assert_eq!(result::<()>(3), Some(()));
+
+        let mut rolled_back = false;
+        assert_eq!(
+            resolve_or_rollback::<u64, _>(0, || rolled_back = true),
+            Some(7)
+        );
+        assert!(!rolled_back);
+        assert_eq!(
+            resolve_or_rollback::<u64, _>(1, || rolled_back = true),
+            None
+        );
+        assert!(rolled_back);
+    }
+
+    #[test]
+    fn unused_amounts() {
+        with_results(
+            "alice",
+            vec![
+                VmPromiseResult::Successful(3u128.to_le_bytes().to_vec()),
+                VmPromiseResult::Failed,
+                VmPromiseResult::Successful(20u128.to_le_bytes().to_vec()),
+            ],
+        );
+        assert_eq!(unused_amount(0, 10), 3);
+        assert_eq!(unused_amount(1, 10), 10);
+        assert_eq!(unused_amount(2, 10), 10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_result_data() {
+        with_results("alice", vec![VmPromiseResult::Successful(vec![1, 2, 3])]);
+        result::<u64>(0);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{env, Balance};
+
+/// One yoctoNEAR, the smallest unit of [`Balance`]. Requiring it attached to a call confirms the
+/// call was signed with a full access key, since function call access keys can't attach deposits.
+pub const ONE_YOCTO: Balance = 1;
+
+/// Aborts if any deposit is attached to the call. Use this for methods which are not payable.
+#[inline]
+pub fn assert_no_deposit() {
+    if env::attached_deposit() != 0 {
+        env::abort();
+    }
+}
+
+/// Aborts unless exactly [`ONE_YOCTO`] is attached to the call. Use this for methods which should
+/// only be called through a full access key, such as transfers.
+#[inline]
+pub fn assert_one_yocto() {
+    if env::attached_deposit() != ONE_YOCTO {
+        env::abort();
+    }
+}
+
+/// Aborts if less than `min` is attached to the call. Returns the attached deposit, which can be
+/// used to refund the amount above `min`.
+#[inline]
+pub fn assert_min_deposit(min: Balance) -> Balance {
+    let deposit = env::attached_deposit();
+    if deposit < min {
+        env::abort();
+    }
+    deposit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::VmContextBuilder;
+    use crate::testing_env;
+
+    fn with_deposit(amount: Balance) {
+        testing_env!(VmContextBuilder::new().attached_deposit(amount).build());
+    }
+
+    #[test]
+    fn deposit_guards_pass() {
+        with_deposit(0);
+        assert_no_deposit();
+
+        with_deposit(ONE_YOCTO);
+        assert_one_yocto();
+
+        with_deposit(10);
+        assert_eq!(assert_min_deposit(10), 10);
+        assert_eq!(assert_min_deposit(5), 10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_deposit_rejects_deposit() {
+        with_deposit(1);
+        assert_no_deposit();
+    }
+
+    #[test]
+    #[should_panic]
+    fn one_yocto_rejects_zero() {
+        with_deposit(0);
+        assert_one_yocto();
+    }
+
+    #[test]
+    #[should_panic]
+    fn one_yocto_rejects_more() {
+        with_deposit(2);
+        assert_one_yocto();
+    }
+
+    #[test]
+    #[should_panic]
+    fn min_deposit_rejects_less() {
+        with_deposit(9);
+        assert_min_deposit(10);
+    }
+}
@@ -5,12 +5,14 @@ use core::mem::{size_of, MaybeUninit};
 /// Register used internally for atomic operations. This register is safe to use by the user,
 /// since it only needs to be untouched while methods of `Environment` execute, which is guaranteed
 /// guest code is not parallel.
-const ATOMIC_OP_REGISTER: u64 = core::u64::MAX - 1;
+const ATOMIC_OP_REGISTER: u64 = u64::MAX - 1;
 /// Register used to record evicted values from the storage.
-const EVICTED_REGISTER: u64 = core::u64::MAX - 2;
+const EVICTED_REGISTER: u64 = u64::MAX - 2;
 
 /// Key used to store the state of the contract.
-const STATE_KEY: &[u8] = b"STATE";
+pub const STATE_KEY: &[u8] = b"STATE";
+/// Key used to store the version of the contract state.
+pub const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
 
 /// A simple macro helper to read blob value coming from host's method.
 macro_rules! try_method_into_register {
@@ -30,6 +32,17 @@ macro_rules! method_into_register {
 /// Index for a batch promise from within the runtime. Used to combine promises within a contract.
 pub struct PromiseIndex(pub u64);
 
+/// Result of a promise which the current execution is a callback of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromiseResult {
+    /// Promise has not finished executing. This is never returned by the current protocol.
+    NotReady,
+    /// Promise succeeded, with the length of the result data written to the buffer.
+    Successful(usize),
+    /// Promise failed.
+    Failed,
+}
+
 /// Aborts the current contract execution without a custom message.
 /// To include a message, use [`panic_str`].
 pub fn abort() -> ! {
@@ -77,7 +90,7 @@ pub(crate) unsafe fn read_register_fixed_64(register_id: u64) -> [u8; 64] {
 /// Returns the size of the register. If register is not used returns `None`.
 pub fn register_len(register_id: u64) -> Option<u64> {
     let len = unsafe { sys::register_len(register_id) };
-    if len == core::u64::MAX {
+    if len == u64::MAX {
         None
     } else {
         Some(len)
@@ -88,19 +101,38 @@ pub fn register_len(register_id: u64) -> Option<u64> {
 // # Context API #
 // ###############
 
+/// Reads an account id from the host `$method` into a stack buffer.
+macro_rules! account_id_from_register {
+    ( $method:ident ) => {{
+        let mut a = Vec::<u8, 64>::new();
+        // Resize buffer to max length before reading bytes into it.
+        a.resize(64, 0).unwrap_or_else(|_| abort());
+        let len = method_into_register!($method, a.as_mut());
+        // Update length for size written
+        unsafe {
+            a.set_len(len);
+            // Fine to cast as account id, should be validated by runtime
+            AccountId::new_raw(a)
+        }
+    }};
+}
+
 // TODO eval this API before releasing
 /// The id of the account that owns the current contract.
 pub fn current_account_id() -> AccountId {
-    let mut a = Vec::<u8, 64>::new();
-    // Resize buffer to max length before reading bytes into it.
-    a.resize(64, 0).unwrap_or_else(|_| abort());
-    let len = method_into_register!(current_account_id, a.as_mut());
-    // Update length for size written
-    unsafe {
-        a.set_len(len);
-        // Fine to cast as account id, should be validated by runtime
-        AccountId::new_raw(a)
-    }
+    account_id_from_register!(current_account_id)
+}
+
+/// The id of the account that either signed the original transaction or issued the initial
+/// cross-contract call.
+pub fn signer_account_id() -> AccountId {
+    account_id_from_register!(signer_account_id)
+}
+
+/// The id of the account that was the previous contract in the chain of cross-contract calls.
+/// If this is the first contract, it is equal to `signer_account_id`.
+pub fn predecessor_account_id() -> AccountId {
+    account_id_from_register!(predecessor_account_id)
 }
 
 /// Current block index.
@@ -246,11 +278,188 @@ pub fn value_return(value: &[u8]) {
 pub fn panic_str(message: &str) -> ! {
     unsafe { sys::panic_utf8(message.len() as _, message.as_ptr() as _) }
 }
+
+/// Terminates the execution of the program with a numeric error code, as the message `E<code>`.
+///
+/// The message is a few bytes written without `core::fmt`, which keeps failures diagnosable on
+/// chain at a fraction of the cost of [`panic_str`] with a full message. Codes are usually declared
+/// with [`error_codes!`](crate::error_codes), and code [`PANIC_CODE`](crate::error::PANIC_CODE) is
+/// used for panics with the `panic-code` feature.
+pub fn abort_with(mut code: u32) -> ! {
+    // `E` followed by at most 10 digits of a `u32`.
+    let mut buf = [0u8; 11];
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (code % 10) as u8;
+        code /= 10;
+        if code == 0 {
+            break;
+        }
+    }
+    i -= 1;
+    buf[i] = b'E';
+    let message = &buf[i..];
+    unsafe { sys::panic_utf8(message.len() as _, message.as_ptr() as _) }
+}
+
 /// Log the UTF-8 encodable message.
+///
+/// This is a no-op when the `disable-logging` feature is enabled.
+#[allow(unused_variables)]
 pub fn log_str(message: &str) {
-    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
-    eprintln!("{}", message);
-    unsafe { sys::log_utf8(message.len() as _, message.as_ptr() as _) }
+    #[cfg(not(feature = "disable-logging"))]
+    {
+        #[cfg(all(feature = "print-logs", not(target_arch = "wasm32")))]
+        eprintln!("{}", message);
+        unsafe { sys::log_utf8(message.len() as _, message.as_ptr() as _) }
+    }
+}
+
+/// Log the UTF-16 encoded message. The length passed to the host is the byte length of the
+/// message, which is twice the number of code units.
+///
+/// This is a no-op when the `disable-logging` feature is enabled.
+#[allow(unused_variables)]
+pub fn log_utf16(message: &[u16]) {
+    #[cfg(not(feature = "disable-logging"))]
+    {
+        #[cfg(all(feature = "print-logs", not(target_arch = "wasm32")))]
+        eprintln!("{}", String::from_utf16_lossy(message));
+        unsafe { sys::log_utf16((message.len() * 2) as _, message.as_ptr() as _) }
+    }
+}
+
+/// Maximum length in bytes of a log message assembled through [`log_parts`].
+pub const LOG_PARTS_BUFFER_LEN: usize = 256;
+
+/// Fragment of a log message that is joined together with others through [`log_parts`].
+///
+/// Numbers are written in decimal form without going through `core::fmt`.
+#[derive(Clone, Copy)]
+pub enum LogPart<'a> {
+    /// String fragment, written as is.
+    Str(&'a str),
+    /// Unsigned integer fragment.
+    U64(u64),
+    /// Signed integer fragment.
+    I64(i64),
+    /// Unsigned 128 bit integer fragment, used for [`Balance`] values.
+    U128(u128),
+}
+
+impl<'a> From<&'a str> for LogPart<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::Str(s)
+    }
+}
+
+impl From<u64> for LogPart<'_> {
+    fn from(v: u64) -> Self {
+        Self::U64(v)
+    }
+}
+
+impl From<i64> for LogPart<'_> {
+    fn from(v: i64) -> Self {
+        Self::I64(v)
+    }
+}
+
+impl From<u128> for LogPart<'_> {
+    fn from(v: u128) -> Self {
+        Self::U128(v)
+    }
+}
+
+/// Writes the decimal representation of `value` to the end of `buf` and returns the start index.
+#[cfg(not(feature = "disable-logging"))]
+fn write_decimal(mut value: u128, buf: &mut [u8; 39]) -> usize {
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            return i;
+        }
+    }
+}
+
+/// Appends the UTF-8 string `bytes` to the message, truncated to the remaining capacity. Returns
+/// `false` if the bytes were truncated.
+///
+/// The string is never cut before a continuation byte, which keeps the message valid UTF-8.
+#[cfg(not(feature = "disable-logging"))]
+fn push_bytes(msg: &mut Vec<u8, LOG_PARTS_BUFFER_LEN>, bytes: &[u8]) -> bool {
+    let mut len = bytes.len().min(LOG_PARTS_BUFFER_LEN - msg.len());
+    while len < bytes.len() && bytes[len] & 0xc0 == 0x80 {
+        len -= 1;
+    }
+    //* The length is limited to the remaining capacity, so this can't fail.
+    let _ = msg.extend_from_slice(&bytes[..len]);
+    len == bytes.len()
+}
+
+/// Appends the decimal `digits` of a number to the message, prefixed with `-` if `negative`.
+/// Returns `false` without appending anything if the number doesn't fit, since a cut number
+/// would log a wrong value.
+#[cfg(not(feature = "disable-logging"))]
+fn push_number(msg: &mut Vec<u8, LOG_PARTS_BUFFER_LEN>, negative: bool, digits: &[u8]) -> bool {
+    if negative as usize + digits.len() > LOG_PARTS_BUFFER_LEN - msg.len() {
+        return false;
+    }
+    //* The number fits in the remaining capacity, so this can't fail.
+    if negative {
+        let _ = msg.push(b'-');
+    }
+    let _ = msg.extend_from_slice(digits);
+    true
+}
+
+/// Joins the message fragments into a single UTF-8 log without allocating or using `core::fmt`.
+/// The message is assembled on the stack and truncated to [`LOG_PARTS_BUFFER_LEN`] bytes, so a
+/// long message never fails the execution. A string fragment is cut at the limit, while a number
+/// is either written whole or dropped. Fragments after the truncated one are dropped.
+///
+/// This is a no-op when the `disable-logging` feature is enabled.
+///
+/// # Example
+/// ```
+/// use nesdie::env::{self, LogPart};
+///
+/// let amount: u128 = 1_000;
+/// env::log_parts(&[LogPart::Str("transferred "), amount.into(), " to bob".into()]);
+/// ```
+#[allow(unused_variables)]
+pub fn log_parts(parts: &[LogPart]) {
+    #[cfg(not(feature = "disable-logging"))]
+    {
+        let mut msg = Vec::<u8, LOG_PARTS_BUFFER_LEN>::new();
+        let mut num_buf = [0u8; 39];
+        for part in parts {
+            let complete = match *part {
+                LogPart::Str(s) => push_bytes(&mut msg, s.as_bytes()),
+                LogPart::U64(v) => {
+                    let start = write_decimal(v as u128, &mut num_buf);
+                    push_number(&mut msg, false, &num_buf[start..])
+                }
+                LogPart::I64(v) => {
+                    let start = write_decimal(v.unsigned_abs() as u128, &mut num_buf);
+                    push_number(&mut msg, v < 0, &num_buf[start..])
+                }
+                LogPart::U128(v) => {
+                    let start = write_decimal(v, &mut num_buf);
+                    push_number(&mut msg, false, &num_buf[start..])
+                }
+            };
+            if !complete {
+                break;
+            }
+        }
+        //* SAFETY: All fragments are either valid UTF-8 strings or ASCII digits.
+        log_str(unsafe { core::str::from_utf8_unchecked(&msg) })
+    }
 }
 
 // ###############
@@ -323,6 +532,20 @@ pub fn state_exists() -> bool {
     storage_has_key(STATE_KEY)
 }
 
+/// Reads the version of the contract state, if one has been written.
+pub fn state_version() -> Option<u32> {
+    let mut buf = [0u8; size_of::<u32>()];
+    match storage_read(STATE_VERSION_KEY, &mut buf)? {
+        4 => Some(u32::from_le_bytes(buf)),
+        _ => abort(),
+    }
+}
+
+/// Writes the version of the contract state, stored next to the state under a static key.
+pub fn state_version_write(version: u32) {
+    storage_write(STATE_VERSION_KEY, &version.to_le_bytes());
+}
+
 //* Promises
 
 // Creates a promise that will execute a method on account with given arguments and attaches
@@ -388,6 +611,31 @@ pub fn promise_then(
 //     }
 // }
 
+/// Number of promise results available to the current execution, if it is a callback.
+pub fn promise_results_count() -> u64 {
+    unsafe { sys::promise_results_count() }
+}
+
+/// Reads the result of the promise at `result_idx` that the current execution is a callback of.
+/// For a successful result, the data is read into `buf`.
+///
+/// Aborts if `buf` is not large enough for the result data, or if there is no result at the index.
+pub fn promise_result(result_idx: u64, buf: &mut [u8]) -> PromiseResult {
+    match unsafe { sys::promise_result(result_idx, ATOMIC_OP_REGISTER) } {
+        0 => PromiseResult::NotReady,
+        1 => PromiseResult::Successful(
+            read_register(ATOMIC_OP_REGISTER, buf).unwrap_or_else(|_| abort()),
+        ),
+        2 => PromiseResult::Failed,
+        _ => abort(),
+    }
+}
+
+/// Uses the result of the promise at `promise_idx` as the result of the current execution.
+pub fn promise_return(promise_idx: PromiseIndex) {
+    unsafe { sys::promise_return(promise_idx.0) }
+}
+
 /// Create a batch promise and return the index of that promise.
 pub fn promise_batch_create(account_id: &str) -> PromiseIndex {
     unsafe {
@@ -537,3 +785,52 @@ const STORAGE_PRICE_PER_BYTE: Balance = 10_000_000_000_000_000_000;
 pub fn storage_byte_cost() -> Balance {
     STORAGE_PRICE_PER_BYTE
 }
+
+#[cfg(all(test, not(feature = "disable-logging")))]
+mod tests {
+    use super::*;
+    use crate::mock::with_mocked_blockchain;
+
+    #[test]
+    fn log_parts_joins_fragments() {
+        log_parts(&[
+            "a".into(),
+            0u64.into(),
+            LogPart::I64(-42),
+            LogPart::U128(u128::MAX),
+            LogPart::I64(i64::MIN),
+        ]);
+        let logs = with_mocked_blockchain(|b| b.logs());
+        assert_eq!(
+            logs.last().unwrap(),
+            &format!("a0-42{}{}", u128::MAX, i64::MIN)
+        );
+    }
+
+    #[test]
+    fn log_parts_truncates_long_message() {
+        let long = "a".repeat(LOG_PARTS_BUFFER_LEN - 1);
+        log_parts(&[long.as_str().into(), "✓".into(), "b".into()]);
+        let logs = with_mocked_blockchain(|b| b.logs());
+        // The multi-byte character doesn't fit, and the fragments after it are dropped.
+        assert_eq!(logs.last().unwrap(), &long);
+
+        // Numbers which don't fit are dropped instead of logging a cut value.
+        log_parts(&[long.as_str().into(), u64::MAX.into(), "b".into()]);
+        let logs = with_mocked_blockchain(|b| b.logs());
+        assert_eq!(logs.last().unwrap(), &long);
+
+        let long = "a".repeat(LOG_PARTS_BUFFER_LEN - 2);
+        log_parts(&[long.as_str().into(), (-1i64).into(), (-1i64).into()]);
+        let logs = with_mocked_blockchain(|b| b.logs());
+        assert_eq!(logs.last().unwrap(), &format!("{}-1", long));
+    }
+
+    #[test]
+    fn log_utf16_message() {
+        let message: std::vec::Vec<u16> = "utf16 ✓".encode_utf16().collect();
+        log_utf16(&message);
+        let logs = with_mocked_blockchain(|b| b.logs());
+        assert_eq!(logs.last().unwrap(), "utf16 ✓");
+    }
+}
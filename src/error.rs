@@ -0,0 +1,69 @@
+use crate::env;
+
+/// Code reported by the panic handler with the `panic-code` feature, which can't be used by
+/// errors declared with [`error_codes!`](crate::error_codes).
+pub const PANIC_CODE: u32 = 0;
+
+/// Error with a numeric code, which is reported when aborting with it through
+/// [`env::abort_with`]. Usually implemented by declaring the error with
+/// [`error_codes!`](crate::error_codes).
+pub trait ErrorCode {
+    /// Numeric code of the error.
+    fn code(&self) -> u32;
+
+    /// Aborts the current execution with the code of the error.
+    fn abort(&self) -> ! {
+        env::abort_with(self.code())
+    }
+}
+
+/// Unwrapping of results whose error has a code, aborting with the code instead of panicking.
+pub trait UnwrapOrAbort<T> {
+    /// Returns the value, or aborts with the code of the error.
+    fn unwrap_or_abort(self) -> T;
+}
+
+impl<T, E: ErrorCode> UnwrapOrAbort<T> for Result<T, E> {
+    fn unwrap_or_abort(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(e) => e.abort(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{catch_contract_panic, ContractError};
+
+    crate::error_codes! {
+        enum TestError {
+            NotFound = 1,
+            Limit = 4_294_967_295,
+        }
+    }
+
+    #[test]
+    fn abort_with_code() {
+        assert_eq!(
+            catch_contract_panic(|| env::abort_with(PANIC_CODE)),
+            Err(ContractError::GuestPanic("E0".to_string()))
+        );
+        assert_eq!(
+            catch_contract_panic(|| TestError::Limit.abort()),
+            Err(ContractError::GuestPanic("E4294967295".to_string()))
+        );
+
+        let result: Result<u8, TestError> = Err(TestError::NotFound);
+        let error = catch_contract_panic(|| result.unwrap_or_abort()).unwrap_err();
+        assert_eq!(error.code(), Some(1));
+        assert_eq!(Ok::<_, TestError>(3).unwrap_or_abort(), 3);
+    }
+
+    #[test]
+    fn std_error() {
+        let error: Box<dyn std::error::Error> = Box::new(TestError::NotFound);
+        assert_eq!(error.to_string(), "E1");
+    }
+}
@@ -0,0 +1,405 @@
+use crate::callback::CallbackValue;
+use crate::env::{self, PromiseIndex, PromiseResult};
+use crate::types::Vec;
+use crate::{AccountId, Balance, Gas};
+use core::marker::PhantomData;
+
+/// Maximum length in bytes of the encoded arguments of a call built with a [`CallBuilder`].
+pub const MAX_ARGS_LEN: usize = 256;
+
+/// Maximum length in bytes of a call result decoded with [`result`], including a length prefix
+/// added by the codec.
+pub const MAX_RESULT_LEN: usize = crate::callback::MAX_CALLBACK_VALUE_LEN + 4;
+
+/// Encoded arguments of a call, written by a [`Codec`].
+pub struct Args {
+    buf: Vec<u8, MAX_ARGS_LEN>,
+}
+
+impl Args {
+    /// Creates empty arguments.
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// Appends `bytes` to the arguments. Aborts if the arguments would be longer than
+    /// [`MAX_ARGS_LEN`].
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf
+            .extend_from_slice(bytes)
+            .unwrap_or_else(|_| env::abort());
+    }
+
+    /// Returns the encoded arguments.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Value which can be passed as an argument of a call. Integers are encoded as their little
+/// endian bytes, which is the counterpart of [`CallbackValue`].
+pub trait ArgValue {
+    /// Length in bytes of the encoding, if values of the type don't all have the same length.
+    /// Codecs which need to frame arguments prefix these values with their length.
+    fn variable_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Writes the encoding of the value.
+    fn write(&self, args: &mut Args);
+}
+
+impl<T: ArgValue + ?Sized> ArgValue for &T {
+    fn variable_len(&self) -> Option<usize> {
+        (**self).variable_len()
+    }
+
+    fn write(&self, args: &mut Args) {
+        (**self).write(args)
+    }
+}
+
+impl ArgValue for () {
+    fn write(&self, _args: &mut Args) {}
+}
+
+impl ArgValue for bool {
+    fn write(&self, args: &mut Args) {
+        args.push(&[*self as u8]);
+    }
+}
+
+macro_rules! impl_arg_value_int {
+    ($($ty:ty),*) => {
+        $(impl ArgValue for $ty {
+            fn write(&self, args: &mut Args) {
+                args.push(&self.to_le_bytes());
+            }
+        })*
+    };
+}
+
+impl_arg_value_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl<const N: usize> ArgValue for [u8; N] {
+    fn write(&self, args: &mut Args) {
+        args.push(self);
+    }
+}
+
+impl ArgValue for [u8] {
+    fn variable_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn write(&self, args: &mut Args) {
+        args.push(self);
+    }
+}
+
+impl ArgValue for str {
+    fn variable_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn write(&self, args: &mut Args) {
+        args.push(self.as_bytes());
+    }
+}
+
+impl ArgValue for AccountId {
+    fn variable_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+
+    fn write(&self, args: &mut Args) {
+        args.push(self.as_bytes());
+    }
+}
+
+/// Encoding of the arguments and result of a call to another contract.
+pub trait Codec {
+    /// Appends `value` to the arguments of a call.
+    fn encode<T: ArgValue + ?Sized>(value: &T, args: &mut Args);
+
+    /// Decodes the result data of a call, returning `None` if the data is invalid.
+    fn decode<T: CallbackValue>(bytes: &[u8]) -> Option<T>;
+}
+
+/// Codec which concatenates the encodings of the arguments, and decodes the result data as is.
+/// This is the layout read by contracts parsing [`env::input`] manually, where at most the last
+/// argument can have a variable length.
+pub enum Raw {}
+
+impl Codec for Raw {
+    fn encode<T: ArgValue + ?Sized>(value: &T, args: &mut Args) {
+        value.write(args);
+    }
+
+    fn decode<T: CallbackValue>(bytes: &[u8]) -> Option<T> {
+        T::decode(bytes)
+    }
+}
+
+/// Codec producing the [Borsh](https://borsh.io) encoding of the arguments, where variable
+/// length values such as account ids are prefixed with their length as a little endian `u32`.
+pub enum Borsh {}
+
+impl Codec for Borsh {
+    fn encode<T: ArgValue + ?Sized>(value: &T, args: &mut Args) {
+        if let Some(len) = value.variable_len() {
+            args.push(&(len as u32).to_le_bytes());
+        }
+        value.write(args);
+    }
+
+    fn decode<T: CallbackValue>(bytes: &[u8]) -> Option<T> {
+        if !T::VARIABLE_LEN {
+            return T::decode(bytes);
+        }
+        if bytes.len() < 4 {
+            return None;
+        }
+        let (len, data) = bytes.split_at(4);
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(len);
+        if u32::from_le_bytes(len_bytes) as usize != data.len() {
+            return None;
+        }
+        T::decode(data)
+    }
+}
+
+/// Call of a method of another contract, with arguments encoded by the codec `C` and a result
+/// of type `T`. Call builders are generated from a trait describing the contract with
+/// [`ext_contract!`](crate::ext_contract), and the call is scheduled with
+/// [`create`](CallBuilder::create) or [`Promise::then`].
+///
+/// The gas attached to the call is always given explicitly, since a call without gas fails on
+/// chain. Shares of the remaining gas can be computed with the [`gas`](crate::gas) module. No
+/// deposit is attached unless set with [`deposit`](CallBuilder::deposit).
+#[must_use]
+pub struct CallBuilder<'a, T, C> {
+    account_id: &'a str,
+    method_name: &'static str,
+    args: Args,
+    amount: Balance,
+    gas: Gas,
+    _marker: PhantomData<fn() -> (T, C)>,
+}
+
+impl<'a, T, C: Codec> CallBuilder<'a, T, C> {
+    /// Creates a call of `method_name` on `account_id` with encoded `args` and `gas` attached.
+    pub fn new(account_id: &'a str, method_name: &'static str, args: Args, gas: Gas) -> Self {
+        Self {
+            account_id,
+            method_name,
+            args,
+            amount: 0,
+            gas,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Attaches `amount` to the call.
+    pub fn deposit(mut self, amount: Balance) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Returns the name of the called method.
+    pub fn method_name(&self) -> &'static str {
+        self.method_name
+    }
+
+    /// Returns the encoded arguments of the call.
+    pub fn args(&self) -> &[u8] {
+        self.args.as_bytes()
+    }
+
+    /// Schedules the call, with [`env::promise_create`].
+    pub fn create(self) -> Promise<T, C> {
+        Promise::new(env::promise_create(
+            self.account_id,
+            self.method_name,
+            self.args.as_bytes(),
+            self.amount,
+            self.gas,
+        ))
+    }
+}
+
+/// Scheduled call with a result of type `T`, decoded with the codec `C`.
+#[must_use]
+pub struct Promise<T, C> {
+    index: PromiseIndex,
+    _marker: PhantomData<fn() -> (T, C)>,
+}
+
+impl<T, C: Codec> Promise<T, C> {
+    /// Wraps the promise at `index`, whose result is of type `T`.
+    pub fn new(index: PromiseIndex) -> Self {
+        Self {
+            index,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the index of the promise.
+    pub fn index(&self) -> &PromiseIndex {
+        &self.index
+    }
+
+    /// Schedules `call` after this promise completes, with [`env::promise_then`]. The result of
+    /// this promise is available to the call through [`result`] at index `0`.
+    pub fn then<U, D: Codec>(self, call: CallBuilder<U, D>) -> Promise<U, D> {
+        Promise::new(env::promise_then(
+            self.index,
+            call.account_id,
+            call.method_name,
+            call.args.as_bytes(),
+            call.amount,
+            call.gas,
+        ))
+    }
+
+    /// Uses the result of the promise as the result of the current execution.
+    pub fn return_value(self) {
+        env::promise_return(self.index)
+    }
+}
+
+/// Reads the result of the promise at `result_idx` and decodes it with the codec `C`. Returns
+/// `None` if the promise failed.
+///
+/// Aborts if there is no result at the index, or if the result data can't be decoded as `T`.
+pub fn result<T: CallbackValue, C: Codec>(result_idx: u64) -> Option<T> {
+    let mut buf = Vec::<u8, MAX_RESULT_LEN>::new();
+    buf.resize(MAX_RESULT_LEN, 0)
+        .unwrap_or_else(|_| env::abort());
+    match env::promise_result(result_idx, &mut buf) {
+        PromiseResult::Successful(len) => {
+            Some(C::decode(&buf[..len]).unwrap_or_else(|| env::abort()))
+        }
+        PromiseResult::Failed => None,
+        PromiseResult::NotReady => env::abort(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::{
+        with_mocked_blockchain, PromiseResult as VmPromiseResult, TestEnv, VmAction,
+    };
+    use crate::AccountId;
+
+    crate::ext_contract! {
+        /// Fungible token contract.
+        #[ext(ext_ft, codec = crate::ext::Borsh)]
+        pub trait FungibleToken {
+            fn ft_transfer(receiver_id: AccountId, amount: u128, memo: &str);
+            fn ft_balance_of(account_id: AccountId) -> u128;
+            fn ft_metadata_owner() -> AccountId;
+        }
+    }
+
+    crate::ext_contract! {
+        #[ext(ext_counter, codec = crate::ext::Raw)]
+        trait Counter {
+            fn add(amount: u64, account_id: AccountId) -> u64;
+        }
+    }
+
+    fn function_calls() -> std::vec::Vec<(String, String, std::vec::Vec<u8>, u128, u64)> {
+        with_mocked_blockchain(|b| {
+            b.created_receipts()
+                .iter()
+                .flat_map(|receipt| {
+                    receipt
+                        .actions
+                        .iter()
+                        .filter_map(move |action| match action {
+                            VmAction::FunctionCall {
+                                method_name,
+                                args,
+                                gas,
+                                deposit,
+                            } => Some((
+                                receipt.receiver_id.clone(),
+                                method_name.clone(),
+                                args.clone(),
+                                *deposit,
+                                *gas,
+                            )),
+                            _ => None,
+                        })
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn encoded_calls() {
+        TestEnv::new().with_clean_storage().set();
+        let bob = AccountId::from("bob");
+
+        let transfer = ext_ft::ft_transfer("ft", 20, &bob, &5, &"hi").deposit(1);
+        assert_eq!(transfer.method_name(), "ft_transfer");
+        let mut args = std::vec::Vec::new();
+        args.extend_from_slice(&[3, 0, 0, 0]);
+        args.extend_from_slice(b"bob");
+        args.extend_from_slice(&5u128.to_le_bytes());
+        args.extend_from_slice(&[2, 0, 0, 0]);
+        args.extend_from_slice(b"hi");
+        assert_eq!(transfer.args(), &args[..]);
+
+        let _ = transfer
+            .create()
+            .then(ext_counter::add("alice", 10, &7, &bob));
+        let mut counter_args = 7u64.to_le_bytes().to_vec();
+        counter_args.extend_from_slice(b"bob");
+        assert_eq!(
+            function_calls(),
+            vec![
+                ("ft".to_string(), "ft_transfer".to_string(), args, 1, 20),
+                ("alice".to_string(), "add".to_string(), counter_args, 0, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn decoded_results() {
+        let mut owner = 5u32.to_le_bytes().to_vec();
+        owner.extend_from_slice(b"alice");
+        TestEnv::new()
+            .promise_results(vec![
+                VmPromiseResult::Successful(9u128.to_le_bytes().to_vec()),
+                VmPromiseResult::Successful(owner),
+                VmPromiseResult::Successful(b"bob".to_vec()),
+                VmPromiseResult::Failed,
+            ])
+            .set();
+        assert_eq!(ext_ft::callback::ft_balance_of(0), Some(9));
+        assert_eq!(ext_ft::callback::ft_metadata_owner(1).unwrap(), "alice");
+        assert_eq!(ext_ft::callback::ft_transfer(3), None);
+        assert_eq!(
+            crate::ext::result::<AccountId, crate::ext::Raw>(2).unwrap(),
+            "bob"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_length_prefix() {
+        TestEnv::new()
+            .promise_results(vec![VmPromiseResult::Successful(b"\x04\0\0\0bob".to_vec())])
+            .set();
+        ext_ft::callback::ft_metadata_owner(0);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::env::{self, PromiseIndex};
+use crate::types::Vec;
+use crate::{Balance, Gas};
+
+/// Gas that is still available to the current execution, i.e. the prepaid gas minus the gas that
+/// has already been used. Unlike subtracting the two values directly, this cannot underflow.
+pub fn remaining_gas() -> Gas {
+    env::prepaid_gas().saturating_sub(env::used_gas())
+}
+
+/// Remaining gas after setting aside `reserved` gas for the rest of the current execution.
+/// Saturates to `0` if less than `reserved` gas is remaining.
+pub fn budget_after(reserved: Gas) -> Gas {
+    remaining_gas().saturating_sub(reserved)
+}
+
+/// Remaining gas after setting aside `reserved` gas for the rest of the current execution.
+/// Aborts the execution if less than `reserved` gas is remaining.
+pub fn require_gas(reserved: Gas) -> Gas {
+    remaining_gas()
+        .checked_sub(reserved)
+        .unwrap_or_else(|| env::abort())
+}
+
+/// Relative weight used to divide unused gas between function calls. A weight of `0` means the
+/// function call will only be attached its static gas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasWeight(pub u64);
+
+/// Splits `total` gas between the `weights` proportionally, writing the share of each weight to
+/// the same index of `out`. Any gas left over from rounding is given to the last non-zero weight,
+/// so that the full amount is always distributed if any weight is non-zero.
+///
+/// Entries of `out` past the length of `weights` are left unchanged. Aborts if `out` is shorter
+/// than `weights`.
+///
+/// # Example
+/// ```
+/// use nesdie::gas::{split_by_weight, GasWeight};
+///
+/// let mut out = [0; 3];
+/// split_by_weight(10, &[GasWeight(1), GasWeight(0), GasWeight(2)], &mut out);
+/// assert_eq!(out, [3, 0, 7]);
+/// ```
+pub fn split_by_weight(total: Gas, weights: &[GasWeight], out: &mut [Gas]) {
+    if out.len() < weights.len() {
+        env::abort();
+    }
+    let total_weight: u128 = weights.iter().map(|w| w.0 as u128).sum();
+    if total_weight == 0 {
+        out[..weights.len()].iter_mut().for_each(|g| *g = 0);
+        return;
+    }
+
+    let mut distributed: Gas = 0;
+    let mut last_weighted = 0;
+    for (i, weight) in weights.iter().enumerate() {
+        //* The share can never be larger than `total`, so casting back to `Gas` is lossless.
+        let share = (total as u128 * weight.0 as u128 / total_weight) as Gas;
+        out[i] = share;
+        distributed += share;
+        if weight.0 != 0 {
+            last_weighted = i;
+        }
+    }
+    out[last_weighted] += total - distributed;
+}
+
+struct WeightedCall<'a> {
+    promise_index: u64,
+    method_name: &'a str,
+    arguments: &'a [u8],
+    amount: Balance,
+    static_gas: Gas,
+    weight: GasWeight,
+}
+
+/// Function calls which are attached gas based on a static amount plus a share of the unused gas
+/// by weight. Calls are only scheduled once [`WeightedCalls::schedule`] is called, since the
+/// unused gas is only known after all calls have been added.
+///
+/// Up to `N` calls can be added, which are kept on the stack.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::gas::{GasWeight, WeightedCalls};
+///
+/// let a = env::promise_batch_create("a.near");
+/// let b = env::promise_batch_create("b.near");
+///
+/// let mut calls = WeightedCalls::<2>::new();
+/// calls.push(&a, "first", &[], 0, 5_000_000_000_000, GasWeight(1));
+/// calls.push(&b, "second", &[], 0, 0, GasWeight(3));
+/// // Keep 10 Tgas for the rest of the current execution.
+/// calls.schedule(10_000_000_000_000);
+/// ```
+pub struct WeightedCalls<'a, const N: usize> {
+    calls: Vec<WeightedCall<'a>, N>,
+}
+
+impl<const N: usize> Default for WeightedCalls<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> WeightedCalls<'a, N> {
+    /// Creates an empty set of function calls.
+    pub const fn new() -> Self {
+        Self { calls: Vec::new() }
+    }
+
+    /// Adds a function call to the batch promise at `promise_index`. The call will be attached
+    /// `static_gas` plus a share of the unused gas based on `weight`.
+    ///
+    /// Aborts if more than `N` calls are added.
+    pub fn push(
+        &mut self,
+        promise_index: &PromiseIndex,
+        method_name: &'a str,
+        arguments: &'a [u8],
+        amount: Balance,
+        static_gas: Gas,
+        weight: GasWeight,
+    ) {
+        self.calls
+            .push(WeightedCall {
+                promise_index: promise_index.0,
+                method_name,
+                arguments,
+                amount,
+                static_gas,
+                weight,
+            })
+            .unwrap_or_else(|_| env::abort());
+    }
+
+    /// Schedules all function calls, keeping `reserved` gas for the rest of the current execution.
+    ///
+    /// Aborts if the remaining gas, after the reservation, does not cover the static gas of all
+    /// calls.
+    pub fn schedule(self, reserved: Gas) {
+        let static_total = self
+            .calls
+            .iter()
+            .try_fold(0 as Gas, |acc, c| acc.checked_add(c.static_gas))
+            .unwrap_or_else(|| env::abort());
+        let unused = require_gas(reserved)
+            .checked_sub(static_total)
+            .unwrap_or_else(|| env::abort());
+
+        let mut weights = [GasWeight(0); N];
+        for (w, call) in weights.iter_mut().zip(self.calls.iter()) {
+            *w = call.weight;
+        }
+        let mut shares = [0 as Gas; N];
+        split_by_weight(unused, &weights, &mut shares);
+
+        for (call, share) in self.calls.iter().zip(shares.iter()) {
+            env::promise_batch_action_function_call(
+                PromiseIndex(call.promise_index),
+                call.method_name,
+                call.arguments,
+                call.amount,
+                call.static_gas + share,
+            );
+        }
+    }
+}
+
+/// Static gas cost of a callback method. This is reserved from the current execution when
+/// scheduling the callback, so that the callback is guaranteed to be attached enough gas.
+///
+/// # Example
+/// ```
+/// use nesdie::{env, gas::CallbackGas};
+///
+/// const RESOLVE_TRANSFER: CallbackGas = CallbackGas::new("resolve_transfer", 5_000_000_000_000);
+///
+/// let transfer = env::promise_create("token.near", "transfer", &[], 1, 10_000_000_000_000);
+/// RESOLVE_TRANSFER.then(transfer, "alice", &[], 0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallbackGas {
+    /// Name of the callback method.
+    pub method_name: &'static str,
+    /// Gas attached to the callback.
+    pub gas: Gas,
+}
+
+impl CallbackGas {
+    /// Declares the static gas cost of a callback method.
+    pub const fn new(method_name: &'static str, gas: Gas) -> Self {
+        Self { method_name, gas }
+    }
+
+    /// Remaining gas after reserving the gas for this callback. Aborts if less than the
+    /// callback's gas is remaining.
+    pub fn budget(&self) -> Gas {
+        require_gas(self.gas)
+    }
+
+    /// Attaches this callback, with its reserved gas, after the promise at `promise_idx`.
+    pub fn then(
+        &self,
+        promise_idx: PromiseIndex,
+        account_id: &str,
+        arguments: &[u8],
+        amount: Balance,
+    ) -> PromiseIndex {
+        env::promise_then(
+            promise_idx,
+            account_id,
+            self.method_name,
+            arguments,
+            amount,
+            self.gas,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{with_mocked_blockchain, VmAction, VmContextBuilder};
+    use crate::testing_env;
+
+    #[test]
+    fn split_distributes_all_gas() {
+        let mut out = [0; 4];
+        split_by_weight(100, &[GasWeight(1); 3], &mut out);
+        assert_eq!(out, [33, 33, 34, 0]);
+
+        // Entries past the weights are left unchanged.
+        split_by_weight(Gas::MAX, &[GasWeight(u64::MAX), GasWeight(1)], &mut out);
+        assert_eq!(out[0] + out[1], Gas::MAX);
+        assert_eq!(out[2..], [34, 0]);
+
+        split_by_weight(100, &[GasWeight(0), GasWeight(0)], &mut out);
+        assert_eq!(out, [0, 0, 34, 0]);
+    }
+
+    #[test]
+    fn budget_saturates() {
+        let prepaid = 10_000_000_000_000;
+        testing_env!(VmContextBuilder::new().prepaid_gas(prepaid).build());
+        assert_eq!(budget_after(u64::MAX), 0);
+        assert!(budget_after(0) < prepaid);
+    }
+
+    #[test]
+    fn weighted_calls_use_unused_gas() {
+        let prepaid = 300_000_000_000_000;
+        testing_env!(VmContextBuilder::new().prepaid_gas(prepaid).build());
+        let a = env::promise_batch_create("a");
+
+        let mut calls = WeightedCalls::<2>::new();
+        calls.push(&a, "first", &[], 0, 1_000, GasWeight(1));
+        calls.push(&a, "second", &[], 0, 0, GasWeight(1));
+        calls.schedule(100_000_000_000_000);
+
+        let gas: std::vec::Vec<Gas> = with_mocked_blockchain(|b| {
+            b.created_receipts()[0]
+                .actions
+                .iter()
+                .map(|a| match a {
+                    VmAction::FunctionCall { gas, .. } => *gas,
+                    _ => unreachable!(),
+                })
+                .collect()
+        });
+        assert_eq!(gas.len(), 2);
+        assert!(gas[0] > 1_000);
+        assert!(gas[0] - 1_000 <= gas[1]);
+        assert!(gas[0] + gas[1] <= prepaid - 100_000_000_000_000);
+    }
+}
@@ -4,17 +4,29 @@
 //! This SDK is setup like a domain-specific language and configures all low level boilerplate
 //! of the contract by default.
 
-#![cfg_attr(target_arch = "wasm32", no_std)]
-#![cfg_attr(
-    all(target_arch = "wasm32", feature = "oom-handler"),
-    feature(alloc_error_handler)
-)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "std")), no_std)]
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 #![deny(dead_code, unused_mut)]
 #![warn(missing_docs)]
 
+/// Machine-readable description of the methods exported by a contract, declared with [`abi!`].
+#[cfg(not(target_arch = "wasm32"))]
+pub mod abi;
+/// Helpers for callbacks of cross-contract calls, which guard and decode promise results.
+pub mod callback;
+/// Guards for the deposit attached to a call, for payable, non-payable and one yocto methods.
+pub mod deposit;
 /// Higher level environment functions which act as a safe wrapper around [`sys`].
 pub mod env;
+/// Numeric error codes, which abort the contract with a few bytes instead of a message.
+pub mod error;
+/// Typed call builders and result decoders for calling other contracts, generated from a trait
+/// with [`ext_contract!`].
+pub mod ext;
+/// Gas metering helpers for budgeting gas across the current execution and scheduled calls.
+pub mod gas;
+/// Contract self-upgrade helpers, which deploy new code and migrate the contract state.
+pub mod upgrade;
 /// Host functions available to a NEAR contract through the runtime the contract is running inside.
 pub use near_sys as sys;
 
@@ -22,24 +34,47 @@ pub use near_sys as sys;
 /// Mock utilities used for testing and overriding the syscall interface for contracts.
 pub mod mock;
 
+// Used by macros which implement `std` traits, as `no_std` crates can't name `std` themselves.
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+#[doc(hidden)]
+pub extern crate std as __std;
+
+mod macros;
 mod types;
 pub use self::types::{AccountId, Balance, Gas};
 
+#[cfg(all(
+    any(
+        feature = "bump-alloc",
+        all(feature = "wee_alloc", not(feature = "std"))
+    ),
+    target_arch = "wasm32"
+))]
+mod allocator;
+
 // Set up global allocator by default if wee_alloc feature is enabled and in wasm32 architecture.
-#[cfg(all(feature = "wee_alloc", target_arch = "wasm32"))]
+// The bump allocator takes precedence, so it can be enabled without disabling default features.
+// Both trap on allocation failure, which avoids the alloc error handler on a stable toolchain.
+// With the `std` feature, the allocator of `std` is used unless the bump allocator is enabled.
+#[cfg(all(
+    feature = "wee_alloc",
+    not(feature = "bump-alloc"),
+    not(feature = "std"),
+    target_arch = "wasm32"
+))]
 #[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+static ALLOC: allocator::TrapOnOom<wee_alloc::WeeAlloc> =
+    allocator::TrapOnOom(wee_alloc::WeeAlloc::INIT);
 
-#[cfg(all(not(feature = "std"), feature = "oom-handler", target_arch = "wasm32"))]
-#[alloc_error_handler]
-fn oom(_: core::alloc::Layout) -> ! {
-    core::arch::wasm32::unreachable()
-}
+#[cfg(all(feature = "bump-alloc", target_arch = "wasm32"))]
+#[global_allocator]
+static ALLOC: allocator::BumpAlloc = allocator::BumpAlloc;
 
 // Update panic handler in wasm32 environments
 #[cfg(all(target_arch = "wasm32", not(feature = "std")))]
 #[panic_handler]
-#[allow(unused_variables)]
+// `payload` is deprecated on newer toolchains, where it is always empty in `no_std`.
+#[allow(unused_variables, deprecated)]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     if cfg!(feature = "panic-message") {
         if let Some(s) = info.payload().downcast_ref::<&str>() {
@@ -47,6 +82,8 @@ fn panic(info: &core::panic::PanicInfo) -> ! {
         } else {
             env::panic_str("unexpected panic occurred");
         }
+    } else if cfg!(feature = "panic-code") {
+        env::abort_with(error::PANIC_CODE)
     } else {
         core::arch::wasm32::unreachable()
     }
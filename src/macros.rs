@@ -0,0 +1,295 @@
+/// Declares the ABI of a contract's exported methods, as an `abi()` function returning an
+/// [`Abi`](crate::abi::Abi). The function is only compiled for non-wasm targets, so declaring the
+/// ABI adds no bytes to the contract. The JSON description can be emitted from a test or build
+/// script with [`Abi::to_json`](crate::abi::Abi::to_json).
+///
+/// Each method is declared with its doc comment, kind, name, input and output:
+/// - kind: `view` for methods which only read state, `call` for methods which change state and
+///   `payable` for methods which change state and accept a deposit.
+/// - input: empty if the method has no input, otherwise the encoding.
+/// - output: omitted if the method returns nothing, otherwise `->` and the encoding.
+///
+/// Encodings are `raw("<field:type>...")` for custom byte layouts, `borsh(Type)` and
+/// `json(Type)`.
+///
+/// # Example
+/// ```
+/// nesdie::abi! {
+///     /// Returns the balance of the account.
+///     view fn balance(borsh(AccountId)) -> borsh(u128);
+///     /// Deposits the attached amount.
+///     payable fn deposit();
+///     /// Transfers the amount to the receiver.
+///     call fn transfer(raw("<amount:u128><receiver_id:bytes>"));
+/// }
+///
+/// # fn main() {
+/// let abi = abi();
+/// assert_eq!(abi.methods.len(), 3);
+/// assert!(abi.method("deposit").unwrap().payable);
+/// println!("{}", abi.to_json());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! abi {
+    ($(
+        $(#[doc = $doc:literal])*
+        $kind:ident fn $name:ident ($($input:ident $input_arg:tt)?) $(-> $output:ident $output_arg:tt)?;
+    )*) => {
+        /// ABI of the methods exported by the contract.
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn abi() -> $crate::abi::Abi {
+            $crate::abi::Abi {
+                methods: $crate::__std::vec![$(
+                    $crate::abi::Method {
+                        name: stringify!($name),
+                        docs: $crate::__std::vec![$($doc),*],
+                        mutability: $crate::__abi_mutability!($kind),
+                        payable: $crate::__abi_payable!($kind),
+                        input: $crate::__abi_encoding!($($input $input_arg)?),
+                        output: $crate::__abi_encoding!($($output $output_arg)?),
+                    }
+                ),*],
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __abi_mutability {
+    (view) => {
+        $crate::abi::Mutability::View
+    };
+    (call) => {
+        $crate::abi::Mutability::Call
+    };
+    (payable) => {
+        $crate::abi::Mutability::Call
+    };
+    ($kind:ident) => {
+        compile_error!(concat!(
+            "unknown method kind `",
+            stringify!($kind),
+            "`, expected `view`, `call` or `payable`"
+        ))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __abi_payable {
+    (payable) => {
+        true
+    };
+    ($kind:ident) => {
+        false
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __abi_encoding {
+    () => {
+        $crate::abi::Encoding::None
+    };
+    (raw($layout:literal)) => {
+        $crate::abi::Encoding::Raw($layout)
+    };
+    (borsh($ty:ty)) => {
+        $crate::abi::Encoding::Borsh(stringify!($ty))
+    };
+    (json($ty:ty)) => {
+        $crate::abi::Encoding::Json(stringify!($ty))
+    };
+}
+
+/// Declares a trait describing the methods of another contract, and generates a module of call
+/// builders for them. Each method gets a function in the module taking the account id of the
+/// contract and the gas to attach, followed by references to the arguments, which returns a
+/// [`CallBuilder`](crate::ext::CallBuilder) with the arguments encoded by the codec. The
+/// `callback` submodule has a function for each method with the same name, which decodes its
+/// result from the promise results with [`ext::result`](crate::ext::result).
+///
+/// Calls and their results are typed, so a misspelled method name or a wrong argument type fails
+/// to compile instead of failing on chain.
+///
+/// The module name and codec are given by the `#[ext(...)]` attribute, with codecs being
+/// [`Borsh`](crate::ext::Borsh), [`Raw`](crate::ext::Raw) or any other
+/// [`Codec`](crate::ext::Codec).
+///
+/// # Example
+/// ```
+/// use nesdie::AccountId;
+///
+/// nesdie::ext_contract! {
+///     /// Fungible token contract.
+///     #[ext(ext_ft, codec = nesdie::ext::Borsh)]
+///     pub trait FungibleToken {
+///         /// Transfers `amount` to `receiver_id`.
+///         fn ft_transfer(receiver_id: AccountId, amount: u128);
+///         /// Returns the balance of `account_id`.
+///         fn ft_balance_of(account_id: AccountId) -> u128;
+///     }
+/// }
+///
+/// # fn main() {
+/// # nesdie::mock::TestEnv::new().set();
+/// let bob = AccountId::from("bob");
+/// let balance = ext_ft::ft_balance_of("token.near", 5_000_000_000_000, &bob);
+/// assert_eq!(balance.args(), b"\x03\0\0\0bob");
+///
+/// // Resolved in a callback with `ext_ft::callback::ft_balance_of(0)`, returning `Option<u128>`.
+/// balance.create().return_value();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ext_contract {
+    (
+        $(#[doc = $doc:literal])*
+        #[ext($module:ident, codec = $codec:path)]
+        $vis:vis trait $name:ident {$(
+            $(#[doc = $method_doc:literal])*
+            fn $method:ident($($arg:ident: $arg_ty:ty),* $(,)?) $(-> $ret:ty)?;
+        )*}
+    ) => {
+        $(#[doc = $doc])*
+        #[allow(dead_code)]
+        $vis trait $name {$(
+            $(#[doc = $method_doc])*
+            fn $method($($arg: $arg_ty),*) $(-> $ret)?;
+        )*}
+
+        #[doc = concat!("Call builders for the methods of [`", stringify!($name), "`].")]
+        #[allow(dead_code)]
+        $vis mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                $(#[doc = $method_doc])*
+                pub fn $method<'a>(
+                    contract_id: &'a str,
+                    gas: $crate::Gas,
+                    $($arg: &$arg_ty),*
+                ) -> $crate::ext::CallBuilder<'a, $crate::__ext_result!($($ret)?), $codec> {
+                    #[allow(unused_mut)]
+                    let mut args = $crate::ext::Args::new();
+                    $(<$codec as $crate::ext::Codec>::encode($arg, &mut args);)*
+                    $crate::ext::CallBuilder::new(contract_id, stringify!($method), args, gas)
+                }
+            )*
+
+            /// Decoders of the results of the methods, for callbacks of the calls.
+            pub mod callback {
+                #[allow(unused_imports)]
+                use super::*;
+
+                $(
+                    #[doc = concat!("Decodes the result of `", stringify!($method), "` at `result_idx`.")]
+                    pub fn $method(result_idx: u64) -> Option<$crate::__ext_result!($($ret)?)> {
+                        $crate::ext::result::<_, $codec>(result_idx)
+                    }
+                )*
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ext_result {
+    () => {
+        ()
+    };
+    ($ret:ty) => {
+        $ret
+    };
+}
+
+/// Declares an enum of contract errors with numeric codes, which implements
+/// [`ErrorCode`](crate::error::ErrorCode) to abort with the code through
+/// [`env::abort_with`](crate::env::abort_with). This keeps failures diagnosable on chain without
+/// the code size of messages or `core::fmt`.
+///
+/// Codes are given explicitly, so they stay stable when variants are added. Code
+/// [`PANIC_CODE`](crate::error::PANIC_CODE) is reserved for panics, and using it fails to compile.
+///
+/// When `std` is available, the enum also implements `Display` as `E<code>` and
+/// `std::error::Error`, so it can be used with error handling of std crates.
+///
+/// # Example
+/// ```
+/// use nesdie::error::{ErrorCode, UnwrapOrAbort};
+///
+/// nesdie::error_codes! {
+///     /// Errors of the token contract.
+///     pub enum TokenError {
+///         /// Sender doesn't have enough balance.
+///         InsufficientBalance = 1,
+///         /// Receiver is not registered.
+///         UnknownReceiver = 2,
+///     }
+/// }
+///
+/// # fn main() {
+/// fn withdraw(balance: u128, amount: u128) -> Result<u128, TokenError> {
+///     balance.checked_sub(amount).ok_or(TokenError::InsufficientBalance)
+/// }
+///
+/// assert_eq!(TokenError::UnknownReceiver.code(), 2);
+/// // Aborts with the message `E1` if the balance is too low.
+/// assert_eq!(withdraw(5, 3).unwrap_or_abort(), 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {$(
+            $(#[$variant_meta:meta])*
+            $variant:ident = $code:literal
+        ),* $(,)?}
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(u32)]
+        $vis enum $name {$(
+            $(#[$variant_meta])*
+            $variant = $code,
+        )*}
+
+        impl $crate::error::ErrorCode for $name {
+            fn code(&self) -> u32 {
+                *self as u32
+            }
+        }
+
+        $crate::__error_codes_std!($name);
+
+        // The code of panics can't be used by an error.
+        $(const _: () = assert!($code != $crate::error::PANIC_CODE);)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+macro_rules! __error_codes_std {
+    ($name:ident) => {
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                write!(f, "E{}", *self as u32)
+            }
+        }
+
+        impl $crate::__std::error::Error for $name {}
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(any(feature = "std", not(target_arch = "wasm32"))))]
+macro_rules! __error_codes_std {
+    ($name:ident) => {};
+}
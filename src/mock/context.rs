@@ -68,6 +68,11 @@ impl VmContextBuilder {
         self
     }
 
+    pub fn input(&mut self, input: Vec<u8>) -> &mut Self {
+        self.context.input = input;
+        self
+    }
+
     pub fn block_index(&mut self, block_index: BlockHeight) -> &mut Self {
         self.context.block_index = block_index;
         self
@@ -0,0 +1,166 @@
+use near_vm_logic::{HostError, VMLogicError};
+use std::cell::RefCell;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+thread_local! {
+    /// Last error returned by a host function of the mocked blockchain, with the name of the
+    /// host function. This is set right before the error is unwrapped into a panic.
+    static LAST_HOST_ERROR: RefCell<Option<(&'static str, VMLogicError)>> = const { RefCell::new(None) };
+}
+
+/// Records the error of the host function `name`, so that the panic it causes can be converted
+/// into a [`ContractError`].
+pub(crate) fn record_host_error(name: &'static str, error: &VMLogicError) {
+    LAST_HOST_ERROR.with(|e| *e.borrow_mut() = Some((name, error.clone())));
+}
+
+/// Reason a contract call failed, caught by [`catch_contract_panic`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractError {
+    /// Contract panicked with a message, through [`env::panic_str`](crate::env::panic_str).
+    GuestPanic(String),
+    /// Contract aborted without a message, through [`env::abort`](crate::env::abort).
+    Abort,
+    /// Prepaid gas was exceeded.
+    GasExceeded,
+    /// Balance of the account was exceeded, for example by attaching more deposit to a promise
+    /// than the account has.
+    BalanceExceeded,
+    /// Any other error of a host function, such as an invalid register or promise index.
+    Host(HostError),
+    /// Error of the mocked blockchain which is not a host error.
+    Other(VMLogicError),
+}
+
+impl ContractError {
+    /// Code of an abort through [`env::abort_with`](crate::env::abort_with), which panics with
+    /// the message `E<code>`.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            ContractError::GuestPanic(message) => {
+                // `parse` alone would also accept a sign, as in `E+5`.
+                let digits = message.strip_prefix('E')?;
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                digits.parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    fn from_host_error(name: &str, error: VMLogicError) -> Self {
+        match error {
+            // `env::abort` calls the `panic` host function natively, which has no message.
+            VMLogicError::HostError(HostError::GuestPanic { .. }) if name == "panic" => {
+                ContractError::Abort
+            }
+            VMLogicError::HostError(HostError::GuestPanic { panic_msg }) => {
+                ContractError::GuestPanic(panic_msg)
+            }
+            VMLogicError::HostError(HostError::GasExceeded) => ContractError::GasExceeded,
+            VMLogicError::HostError(HostError::BalanceExceeded) => ContractError::BalanceExceeded,
+            VMLogicError::HostError(e) => ContractError::Host(e),
+            e => ContractError::Other(e),
+        }
+    }
+}
+
+/// Calls `f`, catching a failure of the contract code it runs against the mocked blockchain as
+/// a [`ContractError`]. This allows asserting the reason of a failure without `#[should_panic]`.
+///
+/// Only failures from host functions are caught. Other panics, such as failed assertions of the
+/// test, are resumed.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{catch_contract_panic, ContractError};
+///
+/// assert_eq!(
+///     catch_contract_panic(|| env::panic_str("not enough balance")),
+///     Err(ContractError::GuestPanic("not enough balance".to_string()))
+/// );
+/// assert_eq!(catch_contract_panic(|| env::abort()), Err(ContractError::Abort));
+/// assert_eq!(catch_contract_panic(|| 7), Ok(7));
+/// ```
+pub fn catch_contract_panic<F, R>(f: F) -> Result<R, ContractError>
+where
+    F: FnOnce() -> R,
+{
+    LAST_HOST_ERROR.with(|e| e.borrow_mut().take());
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        match LAST_HOST_ERROR.with(|e| e.borrow_mut().take()) {
+            Some((name, error)) => ContractError::from_host_error(name, error),
+            None => resume_unwind(payload),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+    use crate::mock::VmContextBuilder;
+    use crate::testing_env;
+
+    #[test]
+    fn contract_errors() {
+        testing_env!(VmContextBuilder::new()
+            .account_balance(10)
+            .prepaid_gas(10u64.pow(13))
+            .build());
+
+        assert_eq!(
+            catch_contract_panic(|| env::panic_str("message")),
+            Err(ContractError::GuestPanic("message".to_string()))
+        );
+        assert_eq!(
+            catch_contract_panic(|| env::abort()),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(
+            catch_contract_panic(|| {
+                env::promise_create("bob", "method", &[], 11, 0);
+            }),
+            Err(ContractError::BalanceExceeded)
+        );
+        assert_eq!(
+            catch_contract_panic(|| env::promise_return(env::PromiseIndex(5))),
+            Err(ContractError::Host(HostError::InvalidPromiseIndex {
+                promise_idx: 5
+            }))
+        );
+        assert_eq!(
+            catch_contract_panic(|| loop {
+                env::storage_write(b"key", &[0; 1024]);
+            }),
+            Err(ContractError::GasExceeded)
+        );
+    }
+
+    #[test]
+    fn error_codes() {
+        let code = |message: &str| ContractError::GuestPanic(message.to_string()).code();
+        assert_eq!(code("E0"), Some(0));
+        assert_eq!(code("E4294967295"), Some(u32::MAX));
+        assert_eq!(code("E+5"), None);
+        assert_eq!(code("E"), None);
+        assert_eq!(code("E4294967296"), None);
+        assert_eq!(code("message"), None);
+        assert_eq!(ContractError::Abort.code(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "test assertion")]
+    fn other_panics_resume() {
+        let _ = catch_contract_panic(|| panic!("test assertion"));
+    }
+
+    #[test]
+    #[should_panic(expected = "test assertion")]
+    fn previous_error_is_cleared() {
+        let _ = catch_contract_panic(|| env::abort());
+        let _ = catch_contract_panic(|| panic!("test assertion"));
+    }
+}
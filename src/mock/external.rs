@@ -1,6 +1,7 @@
 use super::{Receipt, VmAction};
 use crate::types::Balance;
 use near_vm_logic::{External, HostError, ValuePtr};
+use std::cell::Cell;
 use std::collections::HashMap;
 
 type Result<T> = ::core::result::Result<T, near_vm_logic::VMLogicError>;
@@ -12,6 +13,8 @@ pub(crate) struct SdkExternal {
     pub fake_trie: HashMap<Vec<u8>, Vec<u8>>,
     pub receipts: Vec<Receipt>,
     pub validators: HashMap<String, Balance>,
+    /// Estimate of trie nodes touched by storage operations, used for profiling.
+    pub touched_nodes: Cell<u64>,
 }
 
 pub struct MockedValuePtr {
@@ -32,15 +35,24 @@ impl SdkExternal {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Estimates the trie nodes touched to access `key`, which is one node per nibble of the key
+    /// and one for the value.
+    fn touch(&self, key: &[u8]) {
+        self.touched_nodes
+            .set(self.touched_nodes.get() + 2 * key.len() as u64 + 1);
+    }
 }
 
 impl External for SdkExternal {
     fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.touch(key);
         self.fake_trie.insert(key.to_vec(), value.to_vec());
         Ok(())
     }
 
     fn storage_get(&self, key: &[u8]) -> Result<Option<Box<dyn ValuePtr>>> {
+        self.touch(key);
         Ok(self.fake_trie.get(key).map(|value| {
             Box::new(MockedValuePtr {
                 value: value.clone(),
@@ -49,6 +61,7 @@ impl External for SdkExternal {
     }
 
     fn storage_remove(&mut self, key: &[u8]) -> Result<()> {
+        self.touch(key);
         self.fake_trie.remove(key);
         Ok(())
     }
@@ -59,6 +72,7 @@ impl External for SdkExternal {
     }
 
     fn storage_has_key(&mut self, key: &[u8]) -> Result<bool> {
+        self.touch(key);
         Ok(self.fake_trie.contains_key(key))
     }
 
@@ -206,6 +220,7 @@ impl External for SdkExternal {
         Ok(())
     }
 
+    // Touched nodes are only estimated for profiling, and are not charged as gas.
     fn get_touched_nodes_count(&self) -> u64 {
         0
     }
@@ -0,0 +1,236 @@
+use near_vm_logic::{HostError, MemoryLike, VMLogicError};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Invalid access of the guest memory recorded by [`CheckedMemory`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryViolation {
+    /// Accessed range is not inside a registered buffer.
+    OutOfBounds {
+        /// Pointer passed to the host function.
+        offset: u64,
+        /// Length of the accessed range.
+        len: u64,
+    },
+    /// Host function wrote to a buffer registered as read-only.
+    ReadOnly {
+        /// Pointer passed to the host function.
+        offset: u64,
+        /// Length of the written range.
+        len: u64,
+    },
+}
+
+#[derive(Default)]
+struct Buffers {
+    /// Registered buffers by start address, with their length and whether they are writable.
+    buffers: BTreeMap<u64, (u64, bool)>,
+    violations: Vec<MemoryViolation>,
+}
+
+impl Buffers {
+    fn find(&self, offset: u64, len: u64) -> Option<bool> {
+        let end = offset.checked_add(len)?;
+        let (start, (buffer_len, writable)) = self.buffers.range(..=offset).next_back()?;
+        (end <= start + buffer_len).then_some(*writable)
+    }
+}
+
+/// Bounds-checked [`MemoryLike`] for the [`MockedBlockchain`](super::MockedBlockchain), which only
+/// allows host functions to access buffers registered by the test.
+///
+/// The default memory of the mocked blockchain trusts any pointer passed to a host function. With
+/// this memory, accesses outside registered buffers fail with
+/// [`HostError::MemoryAccessViolation`], the error the runtime returns for out of bounds guest
+/// pointers, and are recorded as a [`MemoryViolation`]. Writes to buffers registered with
+/// [`register`](CheckedMemory::register) instead of [`register_mut`](CheckedMemory::register_mut)
+/// fail the same way.
+///
+/// Buffers are not tracked automatically, so only what the test registers is checked. This is
+/// meant for unit tests of code calling [`sys`](crate::sys) functions directly with buffers known
+/// to the test, such as a wrong length passed with a buffer. It can't be used with `env` functions,
+/// whose internal buffers are never registered, and it only reports a write to an immutable
+/// buffer if the test registered that buffer as read-only.
+///
+/// Clones share the registered buffers, so a clone can be kept by the test after passing the
+/// memory to the mocked blockchain.
+///
+/// # Example
+/// ```
+/// use nesdie::mock::{CheckedMemory, MemoryViolation, TestEnv};
+/// use nesdie::sys;
+///
+/// let memory = CheckedMemory::new();
+/// TestEnv::new().memory(memory.clone()).set();
+///
+/// let mut key = *b"key";
+/// memory.register(&key);
+/// unsafe { sys::storage_write(3, key.as_ptr() as u64, 3, key.as_ptr() as u64, 0) };
+///
+/// // The value was read from outside of `key`.
+/// let result = std::panic::catch_unwind(|| unsafe {
+///     sys::storage_write(3, key.as_ptr() as u64, 4, key.as_ptr() as u64, 0)
+/// });
+/// assert!(result.is_err());
+///
+/// // Reading the register writes into `key`, which was registered as read-only.
+/// unsafe { sys::storage_read(3, key.as_ptr() as u64, 0) };
+/// let result = std::panic::catch_unwind(|| unsafe {
+///     sys::read_register(0, key.as_ptr() as u64);
+/// });
+/// assert!(result.is_err());
+///
+/// // Registering the buffer as writable allows reading the register.
+/// memory.register_mut(&mut key);
+/// unsafe { sys::read_register(0, key.as_mut_ptr() as u64) };
+///
+/// let offset = key.as_ptr() as u64;
+/// assert_eq!(
+///     memory.violations(),
+///     vec![
+///         MemoryViolation::OutOfBounds { offset, len: 4 },
+///         MemoryViolation::ReadOnly { offset, len: 3 },
+///     ]
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct CheckedMemory {
+    buffers: Rc<RefCell<Buffers>>,
+}
+
+impl CheckedMemory {
+    /// Creates a memory without any registered buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, ptr: *const u8, len: usize, writable: bool) {
+        self.buffers
+            .borrow_mut()
+            .buffers
+            .insert(ptr as u64, (len as u64, writable));
+    }
+
+    /// Allows host functions to read `buffer`.
+    pub fn register(&self, buffer: &[u8]) {
+        self.insert(buffer.as_ptr(), buffer.len(), false);
+    }
+
+    /// Allows host functions to read and write `buffer`.
+    pub fn register_mut(&self, buffer: &mut [u8]) {
+        self.insert(buffer.as_ptr(), buffer.len(), true);
+    }
+
+    /// Removes the buffer starting at `ptr`. Buffers have to be removed before they are freed,
+    /// otherwise another allocation at the same address would be accessible.
+    pub fn unregister(&self, ptr: *const u8) {
+        self.buffers.borrow_mut().buffers.remove(&(ptr as u64));
+    }
+
+    /// Removes all registered buffers.
+    pub fn clear(&self) {
+        self.buffers.borrow_mut().buffers.clear();
+    }
+
+    /// Returns the invalid accesses since the memory was created, in order.
+    pub fn violations(&self) -> Vec<MemoryViolation> {
+        self.buffers.borrow().violations.clone()
+    }
+
+    fn check_write(&self, offset: u64, len: u64) {
+        let mut buffers = self.buffers.borrow_mut();
+        if buffers.find(offset, len) != Some(true) {
+            buffers
+                .violations
+                .push(MemoryViolation::ReadOnly { offset, len });
+            drop(buffers);
+            let error = VMLogicError::HostError(HostError::MemoryAccessViolation);
+            super::record_host_error("write_memory", &error);
+            panic!("{:?}", error);
+        }
+    }
+}
+
+impl MemoryLike for CheckedMemory {
+    fn fits_memory(&self, offset: u64, len: u64) -> bool {
+        if len == 0 {
+            return true;
+        }
+        let mut buffers = self.buffers.borrow_mut();
+        let fits = buffers.find(offset, len).is_some();
+        if !fits {
+            buffers
+                .violations
+                .push(MemoryViolation::OutOfBounds { offset, len });
+        }
+        fits
+    }
+
+    fn read_memory(&self, offset: u64, buffer: &mut [u8]) {
+        let src = unsafe { std::slice::from_raw_parts(offset as *const u8, buffer.len()) };
+        buffer.copy_from_slice(src);
+    }
+
+    fn read_memory_u8(&self, offset: u64) -> u8 {
+        unsafe { *(offset as *const u8) }
+    }
+
+    fn write_memory(&mut self, offset: u64, buffer: &[u8]) {
+        if buffer.is_empty() {
+            return;
+        }
+        self.check_write(offset, buffer.len() as u64);
+        let dest = unsafe { std::slice::from_raw_parts_mut(offset as *mut u8, buffer.len()) };
+        dest.copy_from_slice(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{catch_contract_panic, ContractError, TestEnv};
+    use crate::sys;
+
+    #[test]
+    fn registered_buffers() {
+        let memory = CheckedMemory::new();
+        TestEnv::new().memory(memory.clone()).set();
+
+        let data = *b"account.near";
+        memory.register(&data);
+        let ptr = data.as_ptr() as u64;
+        assert!(memory.fits_memory(ptr, 12));
+        assert!(memory.fits_memory(ptr + 8, 4));
+        assert!(!memory.fits_memory(ptr + 8, 5));
+        assert!(!memory.fits_memory(ptr - 1, 1));
+        assert!(memory.fits_memory(0, 0));
+        assert!(!memory.fits_memory(u64::MAX, 2));
+
+        // Log of the registered buffer with an invalid length, as a wrong length field would.
+        unsafe { sys::log_utf8(12, ptr) };
+        assert_eq!(
+            catch_contract_panic(|| unsafe { sys::log_utf8(13, ptr) }),
+            Err(ContractError::Host(HostError::MemoryAccessViolation))
+        );
+
+        // Reading a register into a buffer registered as read-only.
+        unsafe { sys::current_account_id(0) };
+        let account = vec![0u8; unsafe { sys::register_len(0) } as usize];
+        memory.register(&account);
+        assert_eq!(
+            catch_contract_panic(|| unsafe { sys::read_register(0, account.as_ptr() as u64) }),
+            Err(ContractError::Host(HostError::MemoryAccessViolation))
+        );
+        memory.unregister(account.as_ptr());
+
+        let mut account = account;
+        memory.register_mut(&mut account);
+        unsafe { sys::read_register(0, account.as_mut_ptr() as u64) };
+        assert_eq!(account, b"alice");
+
+        memory.clear();
+        assert!(!memory.fits_memory(ptr, 1));
+        assert_eq!(memory.violations().len(), 6);
+    }
+}
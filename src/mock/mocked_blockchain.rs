@@ -1,10 +1,10 @@
-use super::{Receipt, SdkExternal};
+use super::{GasProfile, HostFnProfile, Receipt, SdkExternal, StorageDiff, StorageSnapshot};
 use crate::types::Balance;
 use near_primitives_core::runtime::fees::RuntimeFeesConfig;
 use near_vm_logic::mocks::mock_memory::MockedMemory;
 use near_vm_logic::types::PromiseResult;
 use near_vm_logic::types::PromiseResult as VmPromiseResult;
-use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic, VMOutcome};
+use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic, VMLogicError, VMOutcome};
 use std::cell::RefCell;
 use std::collections::HashMap;
 
@@ -36,6 +36,7 @@ fn default_context() -> VMContext {
 /// message.
 pub struct MockedBlockchain {
     logic: RefCell<VMLogic<'static>>,
+    profile: Option<GasProfile>,
     // We keep ownership over logic fixture so that references in `VMLogic` are valid.
     #[allow(dead_code)]
     logic_fixture: LogicFixture,
@@ -79,7 +80,7 @@ impl MockedBlockchain {
         ext.fake_trie = storage;
         ext.validators = validators;
         let memory = memory_opt.unwrap_or_else(|| Box::new(MockedMemory {}));
-        let promise_results = Box::new(promise_results.into_iter().map(From::from).collect());
+        let promise_results = Box::new(promise_results);
         let config = Box::new(config);
         let fees_config = Box::new(fees_config);
 
@@ -107,6 +108,7 @@ impl MockedBlockchain {
         let logic = RefCell::new(logic);
         Self {
             logic,
+            profile: None,
             logic_fixture,
         }
     }
@@ -116,6 +118,22 @@ impl MockedBlockchain {
         &mut self.logic_fixture.ext.fake_trie
     }
 
+    /// Takes a snapshot of the storage, which can be restored or compared against later.
+    pub fn snapshot(&self) -> StorageSnapshot {
+        StorageSnapshot::new(self.logic_fixture.ext.fake_trie.clone())
+    }
+
+    /// Restores the storage to a snapshot. The storage usage of the current execution is not
+    /// changed.
+    pub fn restore(&mut self, snapshot: &StorageSnapshot) {
+        self.logic_fixture.ext.fake_trie = snapshot.storage().clone();
+    }
+
+    /// Returns the keys which changed since the snapshot was taken.
+    pub fn diff(&self, snapshot: &StorageSnapshot) -> StorageDiff {
+        StorageDiff::new(snapshot.storage(), &self.logic_fixture.ext.fake_trie)
+    }
+
     /// Returns slice of created receipts from mocked transactions.
     pub fn created_receipts(&self) -> &[Receipt] {
         &self.logic_fixture.ext.receipts
@@ -135,121 +153,188 @@ impl MockedBlockchain {
     pub fn logs(&self) -> Vec<String> {
         self.outcome().logs
     }
+
+    /// Starts recording the calls, gas and storage usage of each host function in a
+    /// [`GasProfile`]. This replaces any profile recorded before.
+    ///
+    /// The mocked storage is not a trie, so touched trie nodes are estimated as one node per
+    /// nibble of the key and one for the value, for each storage operation. This estimate is only
+    /// reported, and is not charged as gas.
+    pub fn start_profiling(&mut self) {
+        self.profile = Some(GasProfile::default());
+    }
+
+    /// Stops recording and discards the profile.
+    pub fn stop_profiling(&mut self) {
+        self.profile = None;
+    }
+
+    /// Returns the profile recorded since [`MockedBlockchain::start_profiling`], if profiling.
+    pub fn profile(&self) -> Option<GasProfile> {
+        self.profile.clone()
+    }
+
+    /// Replaces the profile, which is used to keep profiling when the mocked blockchain is replaced.
+    pub(super) fn replace_profile(&mut self, profile: Option<GasProfile>) -> Option<GasProfile> {
+        core::mem::replace(&mut self.profile, profile)
+    }
+
+    /// Calls the host function `name` on the logic, recording its usage if profiling.
+    pub(super) fn call_host<F, R>(&mut self, name: &'static str, f: F) -> Result<R, VMLogicError>
+    where
+        F: FnOnce(&mut VMLogic) -> Result<R, VMLogicError>,
+    {
+        let mut logic = self.logic.borrow_mut();
+        let profile = match &mut self.profile {
+            Some(profile) => profile,
+            None => return f(&mut logic),
+        };
+
+        let before = logic.clone_outcome();
+        let nodes_before = self.logic_fixture.ext.touched_nodes.get();
+        let result = f(&mut logic);
+        let after = logic.clone_outcome();
+        profile.record(
+            name,
+            HostFnProfile {
+                calls: 1,
+                gas: after.burnt_gas - before.burnt_gas,
+                storage_bytes: after.storage_usage as i64 - before.storage_usage as i64,
+                trie_nodes: self.logic_fixture.ext.touched_nodes.get() - nodes_before,
+            },
+        );
+        result
+    }
 }
 
+// Host functions are defined with the `C-unwind` ABI so that errors from `VMLogic`, which are
+// unwrapped, can unwind into the test rather than aborting the process.
 #[cfg(not(target_arch = "wasm32"))]
 mod mock_chain {
     use near_vm_logic::{VMLogic, VMLogicError};
 
-    fn with_mock_interface<F, R>(f: F) -> R
+    fn with_mock_interface<F, R>(name: &'static str, f: F) -> R
     where
         F: FnOnce(&mut VMLogic) -> Result<R, VMLogicError>,
     {
-        crate::mock::with_mocked_blockchain(|b| f(&mut b.logic.borrow_mut()).unwrap())
+        crate::mock::with_mocked_blockchain(|b| b.call_host(name, f))
+            .map_err(|e| {
+                crate::mock::record_host_error(name, &e);
+                e
+            })
+            .unwrap()
     }
 
     #[no_mangle]
-    extern "C" fn read_register(register_id: u64, ptr: u64) {
-        with_mock_interface(|b| b.read_register(register_id, ptr))
+    extern "C-unwind" fn read_register(register_id: u64, ptr: u64) {
+        with_mock_interface("read_register", |b| b.read_register(register_id, ptr))
     }
     #[no_mangle]
-    extern "C" fn register_len(register_id: u64) -> u64 {
-        with_mock_interface(|b| b.register_len(register_id))
+    extern "C-unwind" fn register_len(register_id: u64) -> u64 {
+        with_mock_interface("register_len", |b| b.register_len(register_id))
     }
     #[no_mangle]
-    extern "C" fn current_account_id(register_id: u64) {
-        with_mock_interface(|b| b.current_account_id(register_id))
+    extern "C-unwind" fn current_account_id(register_id: u64) {
+        with_mock_interface("current_account_id", |b| b.current_account_id(register_id))
     }
     #[no_mangle]
-    extern "C" fn signer_account_id(register_id: u64) {
-        with_mock_interface(|b| b.signer_account_id(register_id))
+    extern "C-unwind" fn signer_account_id(register_id: u64) {
+        with_mock_interface("signer_account_id", |b| b.signer_account_id(register_id))
     }
     #[no_mangle]
-    extern "C" fn signer_account_pk(register_id: u64) {
-        with_mock_interface(|b| b.signer_account_pk(register_id))
+    extern "C-unwind" fn signer_account_pk(register_id: u64) {
+        with_mock_interface("signer_account_pk", |b| b.signer_account_pk(register_id))
     }
     #[no_mangle]
-    extern "C" fn predecessor_account_id(register_id: u64) {
-        with_mock_interface(|b| b.predecessor_account_id(register_id))
+    extern "C-unwind" fn predecessor_account_id(register_id: u64) {
+        with_mock_interface("predecessor_account_id", |b| {
+            b.predecessor_account_id(register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn input(register_id: u64) {
-        with_mock_interface(|b| b.input(register_id))
+    extern "C-unwind" fn input(register_id: u64) {
+        with_mock_interface("input", |b| b.input(register_id))
     }
     #[no_mangle]
-    extern "C" fn block_index() -> u64 {
-        with_mock_interface(|b| b.block_index())
+    extern "C-unwind" fn block_index() -> u64 {
+        with_mock_interface("block_index", |b| b.block_index())
     }
     #[no_mangle]
-    extern "C" fn block_timestamp() -> u64 {
-        with_mock_interface(|b| b.block_timestamp())
+    extern "C-unwind" fn block_timestamp() -> u64 {
+        with_mock_interface("block_timestamp", |b| b.block_timestamp())
     }
     #[no_mangle]
-    extern "C" fn epoch_height() -> u64 {
-        with_mock_interface(|b| b.epoch_height())
+    extern "C-unwind" fn epoch_height() -> u64 {
+        with_mock_interface("epoch_height", |b| b.epoch_height())
     }
     #[no_mangle]
-    extern "C" fn storage_usage() -> u64 {
-        with_mock_interface(|b| b.storage_usage())
+    extern "C-unwind" fn storage_usage() -> u64 {
+        with_mock_interface("storage_usage", |b| b.storage_usage())
     }
     #[no_mangle]
-    extern "C" fn account_balance(balance_ptr: u64) {
-        with_mock_interface(|b| b.account_balance(balance_ptr))
+    extern "C-unwind" fn account_balance(balance_ptr: u64) {
+        with_mock_interface("account_balance", |b| b.account_balance(balance_ptr))
     }
     #[no_mangle]
-    extern "C" fn account_locked_balance(balance_ptr: u64) {
-        with_mock_interface(|b| b.account_locked_balance(balance_ptr))
+    extern "C-unwind" fn account_locked_balance(balance_ptr: u64) {
+        with_mock_interface("account_locked_balance", |b| {
+            b.account_locked_balance(balance_ptr)
+        })
     }
     #[no_mangle]
-    extern "C" fn attached_deposit(balance_ptr: u64) {
-        with_mock_interface(|b| b.attached_deposit(balance_ptr))
+    extern "C-unwind" fn attached_deposit(balance_ptr: u64) {
+        with_mock_interface("attached_deposit", |b| b.attached_deposit(balance_ptr))
     }
     #[no_mangle]
-    extern "C" fn prepaid_gas() -> u64 {
-        with_mock_interface(|b| b.prepaid_gas())
+    extern "C-unwind" fn prepaid_gas() -> u64 {
+        with_mock_interface("prepaid_gas", |b| b.prepaid_gas())
     }
     #[no_mangle]
-    extern "C" fn used_gas() -> u64 {
-        with_mock_interface(|b| b.used_gas())
+    extern "C-unwind" fn used_gas() -> u64 {
+        with_mock_interface("used_gas", |b| b.used_gas())
     }
     #[no_mangle]
-    extern "C" fn random_seed(register_id: u64) {
-        with_mock_interface(|b| b.random_seed(register_id))
+    extern "C-unwind" fn random_seed(register_id: u64) {
+        with_mock_interface("random_seed", |b| b.random_seed(register_id))
     }
     #[no_mangle]
-    extern "C" fn sha256(value_len: u64, value_ptr: u64, register_id: u64) {
-        with_mock_interface(|b| b.sha256(value_len, value_ptr, register_id))
+    extern "C-unwind" fn sha256(value_len: u64, value_ptr: u64, register_id: u64) {
+        with_mock_interface("sha256", |b| b.sha256(value_len, value_ptr, register_id))
     }
     #[no_mangle]
-    extern "C" fn keccak256(value_len: u64, value_ptr: u64, register_id: u64) {
-        with_mock_interface(|b| b.keccak256(value_len, value_ptr, register_id))
+    extern "C-unwind" fn keccak256(value_len: u64, value_ptr: u64, register_id: u64) {
+        with_mock_interface("keccak256", |b| {
+            b.keccak256(value_len, value_ptr, register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn keccak512(value_len: u64, value_ptr: u64, register_id: u64) {
-        with_mock_interface(|b| b.keccak512(value_len, value_ptr, register_id))
+    extern "C-unwind" fn keccak512(value_len: u64, value_ptr: u64, register_id: u64) {
+        with_mock_interface("keccak512", |b| {
+            b.keccak512(value_len, value_ptr, register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn value_return(value_len: u64, value_ptr: u64) {
-        with_mock_interface(|b| b.value_return(value_len, value_ptr))
+    extern "C-unwind" fn value_return(value_len: u64, value_ptr: u64) {
+        with_mock_interface("value_return", |b| b.value_return(value_len, value_ptr))
     }
     #[no_mangle]
-    extern "C" fn panic() {
-        with_mock_interface(|b| b.panic())
+    extern "C-unwind" fn panic() {
+        with_mock_interface("panic", |b| b.panic())
     }
     #[no_mangle]
-    extern "C" fn panic_utf8(len: u64, ptr: u64) {
-        with_mock_interface(|b| b.panic_utf8(len, ptr))
+    extern "C-unwind" fn panic_utf8(len: u64, ptr: u64) {
+        with_mock_interface("panic_utf8", |b| b.panic_utf8(len, ptr))
     }
     #[no_mangle]
-    extern "C" fn log_utf8(len: u64, ptr: u64) {
-        with_mock_interface(|b| b.log_utf8(len, ptr))
+    extern "C-unwind" fn log_utf8(len: u64, ptr: u64) {
+        with_mock_interface("log_utf8", |b| b.log_utf8(len, ptr))
     }
     #[no_mangle]
-    extern "C" fn log_utf16(len: u64, ptr: u64) {
-        with_mock_interface(|b| b.log_utf16(len, ptr))
+    extern "C-unwind" fn log_utf16(len: u64, ptr: u64) {
+        with_mock_interface("log_utf16", |b| b.log_utf16(len, ptr))
     }
     #[no_mangle]
-    extern "C" fn promise_create(
+    extern "C-unwind" fn promise_create(
         account_id_len: u64,
         account_id_ptr: u64,
         method_name_len: u64,
@@ -259,7 +344,7 @@ mod mock_chain {
         amount_ptr: u64,
         gas: u64,
     ) -> u64 {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_create", |b| {
             b.promise_create(
                 account_id_len,
                 account_id_ptr,
@@ -273,7 +358,7 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_then(
+    extern "C-unwind" fn promise_then(
         promise_index: u64,
         account_id_len: u64,
         account_id_ptr: u64,
@@ -284,7 +369,7 @@ mod mock_chain {
         amount_ptr: u64,
         gas: u64,
     ) -> u64 {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_then", |b| {
             b.promise_then(
                 promise_index,
                 account_id_len,
@@ -299,37 +384,45 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_and(promise_idx_ptr: u64, promise_idx_count: u64) -> u64 {
-        with_mock_interface(|b| b.promise_and(promise_idx_ptr, promise_idx_count))
+    extern "C-unwind" fn promise_and(promise_idx_ptr: u64, promise_idx_count: u64) -> u64 {
+        with_mock_interface("promise_and", |b| {
+            b.promise_and(promise_idx_ptr, promise_idx_count)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_create(account_id_len: u64, account_id_ptr: u64) -> u64 {
-        with_mock_interface(|b| b.promise_batch_create(account_id_len, account_id_ptr))
+    extern "C-unwind" fn promise_batch_create(account_id_len: u64, account_id_ptr: u64) -> u64 {
+        with_mock_interface("promise_batch_create", |b| {
+            b.promise_batch_create(account_id_len, account_id_ptr)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_then(
+    extern "C-unwind" fn promise_batch_then(
         promise_index: u64,
         account_id_len: u64,
         account_id_ptr: u64,
     ) -> u64 {
-        with_mock_interface(|b| b.promise_batch_then(promise_index, account_id_len, account_id_ptr))
+        with_mock_interface("promise_batch_then", |b| {
+            b.promise_batch_then(promise_index, account_id_len, account_id_ptr)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_create_account(promise_index: u64) {
-        with_mock_interface(|b| b.promise_batch_action_create_account(promise_index))
+    extern "C-unwind" fn promise_batch_action_create_account(promise_index: u64) {
+        with_mock_interface("promise_batch_action_create_account", |b| {
+            b.promise_batch_action_create_account(promise_index)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_deploy_contract(
+    extern "C-unwind" fn promise_batch_action_deploy_contract(
         promise_index: u64,
         code_len: u64,
         code_ptr: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_deploy_contract", |b| {
             b.promise_batch_action_deploy_contract(promise_index, code_len, code_ptr)
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_function_call(
+    extern "C-unwind" fn promise_batch_action_function_call(
         promise_index: u64,
         method_name_len: u64,
         method_name_ptr: u64,
@@ -338,7 +431,7 @@ mod mock_chain {
         amount_ptr: u64,
         gas: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_function_call", |b| {
             b.promise_batch_action_function_call(
                 promise_index,
                 method_name_len,
@@ -351,28 +444,30 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_transfer(promise_index: u64, amount_ptr: u64) {
-        with_mock_interface(|b| b.promise_batch_action_transfer(promise_index, amount_ptr))
+    extern "C-unwind" fn promise_batch_action_transfer(promise_index: u64, amount_ptr: u64) {
+        with_mock_interface("promise_batch_action_transfer", |b| {
+            b.promise_batch_action_transfer(promise_index, amount_ptr)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_stake(
+    extern "C-unwind" fn promise_batch_action_stake(
         promise_index: u64,
         amount_ptr: u64,
         public_key_len: u64,
         public_key_ptr: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_stake", |b| {
             b.promise_batch_action_stake(promise_index, amount_ptr, public_key_len, public_key_ptr)
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_add_key_with_full_access(
+    extern "C-unwind" fn promise_batch_action_add_key_with_full_access(
         promise_index: u64,
         public_key_len: u64,
         public_key_ptr: u64,
         nonce: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_add_key_with_full_access", |b| {
             b.promise_batch_action_add_key_with_full_access(
                 promise_index,
                 public_key_len,
@@ -382,7 +477,7 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_add_key_with_function_call(
+    extern "C-unwind" fn promise_batch_action_add_key_with_function_call(
         promise_index: u64,
         public_key_len: u64,
         public_key_ptr: u64,
@@ -393,7 +488,7 @@ mod mock_chain {
         method_names_len: u64,
         method_names_ptr: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_add_key_with_function_call", |b| {
             b.promise_batch_action_add_key_with_function_call(
                 promise_index,
                 public_key_len,
@@ -408,22 +503,22 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_delete_key(
+    extern "C-unwind" fn promise_batch_action_delete_key(
         promise_index: u64,
         public_key_len: u64,
         public_key_ptr: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_delete_key", |b| {
             b.promise_batch_action_delete_key(promise_index, public_key_len, public_key_ptr)
         })
     }
     #[no_mangle]
-    extern "C" fn promise_batch_action_delete_account(
+    extern "C-unwind" fn promise_batch_action_delete_account(
         promise_index: u64,
         beneficiary_id_len: u64,
         beneficiary_id_ptr: u64,
     ) {
-        with_mock_interface(|b| {
+        with_mock_interface("promise_batch_action_delete_account", |b| {
             b.promise_batch_action_delete_account(
                 promise_index,
                 beneficiary_id_len,
@@ -432,47 +527,57 @@ mod mock_chain {
         })
     }
     #[no_mangle]
-    extern "C" fn promise_results_count() -> u64 {
-        with_mock_interface(|b| b.promise_results_count())
+    extern "C-unwind" fn promise_results_count() -> u64 {
+        with_mock_interface("promise_results_count", |b| b.promise_results_count())
     }
     #[no_mangle]
-    extern "C" fn promise_result(result_idx: u64, register_id: u64) -> u64 {
-        with_mock_interface(|b| b.promise_result(result_idx, register_id))
+    extern "C-unwind" fn promise_result(result_idx: u64, register_id: u64) -> u64 {
+        with_mock_interface("promise_result", |b| {
+            b.promise_result(result_idx, register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn promise_return(promise_id: u64) {
-        with_mock_interface(|b| b.promise_return(promise_id))
+    extern "C-unwind" fn promise_return(promise_id: u64) {
+        with_mock_interface("promise_return", |b| b.promise_return(promise_id))
     }
     #[no_mangle]
-    extern "C" fn storage_write(
+    extern "C-unwind" fn storage_write(
         key_len: u64,
         key_ptr: u64,
         value_len: u64,
         value_ptr: u64,
         register_id: u64,
     ) -> u64 {
-        with_mock_interface(|b| {
+        with_mock_interface("storage_write", |b| {
             b.storage_write(key_len, key_ptr, value_len, value_ptr, register_id)
         })
     }
     #[no_mangle]
-    extern "C" fn storage_read(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
-        with_mock_interface(|b| b.storage_read(key_len, key_ptr, register_id))
+    extern "C-unwind" fn storage_read(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
+        with_mock_interface("storage_read", |b| {
+            b.storage_read(key_len, key_ptr, register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn storage_remove(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
-        with_mock_interface(|b| b.storage_remove(key_len, key_ptr, register_id))
+    extern "C-unwind" fn storage_remove(key_len: u64, key_ptr: u64, register_id: u64) -> u64 {
+        with_mock_interface("storage_remove", |b| {
+            b.storage_remove(key_len, key_ptr, register_id)
+        })
     }
     #[no_mangle]
-    extern "C" fn storage_has_key(key_len: u64, key_ptr: u64) -> u64 {
-        with_mock_interface(|b| b.storage_has_key(key_len, key_ptr))
+    extern "C-unwind" fn storage_has_key(key_len: u64, key_ptr: u64) -> u64 {
+        with_mock_interface("storage_has_key", |b| b.storage_has_key(key_len, key_ptr))
     }
     #[no_mangle]
-    extern "C" fn validator_stake(account_id_len: u64, account_id_ptr: u64, stake_ptr: u64) {
-        with_mock_interface(|b| b.validator_stake(account_id_len, account_id_ptr, stake_ptr))
+    extern "C-unwind" fn validator_stake(account_id_len: u64, account_id_ptr: u64, stake_ptr: u64) {
+        with_mock_interface("validator_stake", |b| {
+            b.validator_stake(account_id_len, account_id_ptr, stake_ptr)
+        })
     }
     #[no_mangle]
-    extern "C" fn validator_total_stake(stake_ptr: u64) {
-        with_mock_interface(|b| b.validator_total_stake(stake_ptr))
+    extern "C-unwind" fn validator_total_stake(stake_ptr: u64) {
+        with_mock_interface("validator_total_stake", |b| {
+            b.validator_total_stake(stake_ptr)
+        })
     }
 }
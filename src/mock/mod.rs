@@ -1,17 +1,35 @@
 mod context;
+mod error;
 mod external;
+mod memory;
 mod mocked_blockchain;
+mod profile;
+mod property;
 mod receipt;
+mod simulator;
+mod storage;
+mod test_env;
+mod wasm;
 
+pub(crate) use self::error::record_host_error;
+pub use self::error::{catch_contract_panic, ContractError};
 pub(crate) use self::external::SdkExternal;
+pub use self::memory::{CheckedMemory, MemoryViolation};
 pub use self::mocked_blockchain::MockedBlockchain;
+pub use self::profile::{GasProfile, HostFnProfile};
+pub use self::property::{Call, Gen, PropertyFailure, PropertyTest};
 pub use self::receipt::{Receipt, VmAction};
+pub use self::simulator::{ContractFn, SimAccount, SimOutcome, SimStatus, Simulator};
+pub use self::storage::{StorageChange, StorageDiff, StorageSnapshot};
+pub use self::test_env::TestEnv;
+pub use self::wasm::{WasmContract, WasmError, WasmOutcome};
 pub use context::VmContextBuilder;
 use core::cell::RefCell;
 
 // TODO I'd like to remove this export
 pub use near_primitives_core::runtime::fees::RuntimeFeesConfig;
-pub use near_vm_logic::{VMConfig, VMContext};
+pub use near_vm_logic::types::{PromiseResult, ReturnData};
+pub use near_vm_logic::{HostError, VMConfig, VMContext, VMLogicError, VMOutcome};
 
 thread_local! {
     /// Low-level blockchain interface wrapped by the environment. Prefer using `env::*` and
@@ -27,17 +45,18 @@ thread_local! {
 /// There are five parameters that can be accepted to configure the interface with a
 /// [`MockedBlockchain`], in this order:
 /// - `context`: [`VMContext`] which contains some core information about
-/// the blockchain and message data which can be used from the smart contract.
+///   the blockchain and message data which can be used from the smart contract.
 /// - `config` (optional): [`VMConfig`] which contains some additional information
-/// about the VM to configure parameters not directly related to the transaction being executed.
+///   about the VM to configure parameters not directly related to the transaction being executed.
 /// - `fee_config`(optional): [`RuntimeFeesConfig`] which configures the
-/// fees for execution and storage of transactions.
+///   fees for execution and storage of transactions.
 /// - `validators`(optional): a [`HashMap`]<[`AccountId`], [`Balance`]> mocking the
-/// current validators of the blockchain.
+///   current validators of the blockchain.
 /// - `promise_results`(optional): a [`Vec`] of [`PromiseResult`] which mocks the results
-/// of callback calls during the execution.
+///   of callback calls during the execution.
 ///
-/// Any argument not included will use the default implementation of each.
+/// Any argument not included will use the default implementation of each. [`TestEnv`] covers the
+/// same configuration with named builder methods, and also allows controlling storage and blocks.
 ///
 /// # Example use
 ///
@@ -65,12 +84,12 @@ thread_local! {
 /// ```
 ///
 /// [`MockedBlockchain`]: crate::mock::MockedBlockchain
-/// [`VMContext`]: crate::VMContext
-/// [`VMConfig`]: crate::VMConfig
-/// [`RuntimeFeesConfig`]: crate::RuntimeFeesConfig
+/// [`VMContext`]: crate::mock::VMContext
+/// [`VMConfig`]: crate::mock::VMConfig
+/// [`RuntimeFeesConfig`]: crate::mock::RuntimeFeesConfig
 /// [`AccountId`]: crate::AccountId
 /// [`Balance`]: crate::Balance
-/// [`PromiseResult`]: crate::PromiseResult
+/// [`PromiseResult`]: crate::mock::PromiseResult
 /// [`HashMap`]: std::collections::HashMap
 #[macro_export]
 macro_rules! testing_env {
@@ -0,0 +1,153 @@
+use crate::Gas;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Usage recorded for a single host function in a [`GasProfile`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HostFnProfile {
+    /// Number of calls to the host function.
+    pub calls: u64,
+    /// Gas burnt by the calls.
+    pub gas: Gas,
+    /// Change in storage usage in bytes caused by the calls.
+    pub storage_bytes: i64,
+    /// Estimate of trie nodes touched by the calls. See [`MockedBlockchain::start_profiling`].
+    ///
+    /// [`MockedBlockchain::start_profiling`]: super::MockedBlockchain::start_profiling
+    pub trie_nodes: u64,
+}
+
+impl HostFnProfile {
+    fn add(&mut self, other: &HostFnProfile) {
+        self.calls += other.calls;
+        self.gas += other.gas;
+        self.storage_bytes += other.storage_bytes;
+        self.trie_nodes += other.trie_nodes;
+    }
+}
+
+/// Per host function breakdown of the gas and storage used while profiling the
+/// [`MockedBlockchain`](super::MockedBlockchain).
+///
+/// The [`Display`](fmt::Display) implementation formats the profile as a table with one line per
+/// host function, sorted by name, so that a profile checked into the repository can be compared
+/// against in tests to catch gas regressions.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{with_mocked_blockchain, VmContextBuilder};
+/// use nesdie::testing_env;
+///
+/// testing_env!(VmContextBuilder::new().build());
+/// with_mocked_blockchain(|b| b.start_profiling());
+///
+/// env::storage_write(b"key", b"value");
+/// env::log_str("written");
+///
+/// let profile = with_mocked_blockchain(|b| b.profile()).unwrap();
+/// assert_eq!(profile.get("storage_write").unwrap().calls, 1);
+/// assert_eq!(profile.get("storage_write").unwrap().storage_bytes, 48);
+/// assert!(profile.total().gas < 100_000_000_000);
+/// println!("{}", profile);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasProfile {
+    host_fns: BTreeMap<&'static str, HostFnProfile>,
+}
+
+impl GasProfile {
+    /// Records a call to the host function `name`.
+    pub(crate) fn record(&mut self, name: &'static str, usage: HostFnProfile) {
+        self.host_fns.entry(name).or_default().add(&usage);
+    }
+
+    /// Returns the usage of the host function `name`, if it was called.
+    pub fn get(&self, name: &str) -> Option<&HostFnProfile> {
+        self.host_fns.get(name)
+    }
+
+    /// Returns an iterator over the usage of each called host function, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &HostFnProfile)> {
+        self.host_fns.iter().map(|(name, usage)| (*name, usage))
+    }
+
+    /// Returns the usage of all host functions combined.
+    pub fn total(&self) -> HostFnProfile {
+        let mut total = HostFnProfile::default();
+        for usage in self.host_fns.values() {
+            total.add(usage);
+        }
+        total
+    }
+}
+
+impl fmt::Display for GasProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn line(f: &mut fmt::Formatter, name: &str, usage: &HostFnProfile) -> fmt::Result {
+            writeln!(
+                f,
+                "{:<48} {:>8} {:>16} {:>8} {:>10}",
+                name, usage.calls, usage.gas, usage.storage_bytes, usage.trie_nodes
+            )
+        }
+        writeln!(
+            f,
+            "{:<48} {:>8} {:>16} {:>8} {:>10}",
+            "host function", "calls", "gas", "storage", "trie nodes"
+        )?;
+        for (name, usage) in self.iter() {
+            line(f, name, usage)?;
+        }
+        line(f, "total", &self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::env;
+    use crate::mock::{with_mocked_blockchain, VmContextBuilder};
+    use crate::testing_env;
+
+    #[test]
+    fn profile_host_functions() {
+        testing_env!(VmContextBuilder::new().build());
+        env::storage_write(b"key", b"value");
+        assert!(with_mocked_blockchain(|b| b.profile()).is_none());
+
+        let start_gas = with_mocked_blockchain(|b| b.outcome().burnt_gas);
+        with_mocked_blockchain(|b| b.start_profiling());
+        env::storage_write(b"key", b"value2");
+        env::storage_write(b"other", b"value");
+        assert!(env::storage_remove(b"other"));
+        env::log_str("done");
+
+        let profile = with_mocked_blockchain(|b| b.profile()).unwrap();
+        let write = profile.get("storage_write").unwrap();
+        assert_eq!(write.calls, 2);
+        assert_eq!(write.storage_bytes, 1 + 50);
+        // Writes read the previous value, so each key is touched twice.
+        assert_eq!(write.trie_nodes, 2 * (7 + 11));
+        assert!(write.gas > 0);
+        let remove = profile.get("storage_remove").unwrap();
+        assert_eq!(remove.storage_bytes, -50);
+        assert_eq!(profile.get("log_utf8").unwrap().calls, 1);
+        assert!(profile.get("storage_read").is_none());
+
+        let total = profile.total();
+        assert_eq!(total.calls, 4);
+        assert_eq!(total.storage_bytes, 1);
+        assert_eq!(
+            total.gas,
+            with_mocked_blockchain(|b| b.outcome().burnt_gas) - start_gas
+        );
+
+        let report = profile.to_string();
+        assert_eq!(report.lines().count(), 5);
+        assert!(report.lines().nth(3).unwrap().starts_with("storage_write "));
+        assert!(report.lines().last().unwrap().starts_with("total "));
+
+        with_mocked_blockchain(|b| b.stop_profiling());
+        assert!(with_mocked_blockchain(|b| b.profile()).is_none());
+    }
+}
@@ -0,0 +1,502 @@
+use super::{with_mocked_blockchain, TestEnv, VmContextBuilder};
+use crate::types::Balance;
+use near_vm_logic::VMContext;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::fmt;
+use std::ops::Range;
+
+/// Source of random values passed to the generators of a [`PropertyTest`].
+///
+/// Values are drawn from a generator seeded with the `u64` seed of the run, so a sequence can be
+/// reproduced from the seed reported in a [`PropertyFailure`].
+pub struct Gen {
+    rng: XorShiftRng,
+}
+
+impl Gen {
+    /// Creates a source seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: XorShiftRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Returns a random `u64`.
+    pub fn u64(&mut self) -> u64 {
+        self.rng.gen()
+    }
+
+    /// Returns a random value in `range`. Panics if the range is empty.
+    pub fn range(&mut self, range: Range<u64>) -> u64 {
+        self.rng.gen_range(range.start, range.end)
+    }
+
+    /// Returns a random index below `len`. Panics if `len` is zero.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.rng.gen_range(0, len)
+    }
+
+    /// Returns a random `bool`.
+    pub fn bool(&mut self) -> bool {
+        self.rng.gen()
+    }
+}
+
+/// Call of a sequence generated by a [`PropertyTest`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call<A> {
+    /// Signer and predecessor of the call.
+    pub predecessor: String,
+    /// Deposit attached to the call.
+    pub deposit: Balance,
+    /// Generated action, which determines the method and input of the call.
+    pub action: A,
+}
+
+/// Minimal sequence of calls found by a [`PropertyTest`] which breaks an invariant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyFailure<A> {
+    /// Seed of the run which found the failure.
+    pub seed: u64,
+    /// Name of the broken invariant.
+    pub invariant: &'static str,
+    /// Calls after the setup which reproduce the failure. The invariant is broken after the last
+    /// call.
+    pub calls: Vec<Call<A>>,
+}
+
+impl<A: fmt::Debug> fmt::Display for PropertyFailure<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invariant `{}` failed with seed {} after {} calls",
+            self.invariant,
+            self.seed,
+            self.calls.len()
+        )?;
+        for (i, call) in self.calls.iter().enumerate() {
+            write!(
+                f,
+                "\n{}. {} (deposit {}): {:?}",
+                i + 1,
+                call.predecessor,
+                call.deposit,
+                call.action
+            )?;
+        }
+        Ok(())
+    }
+}
+
+type Invariant = (&'static str, Box<dyn Fn() -> bool>);
+type Shrink<A> = Box<dyn Fn(&A) -> Vec<A>>;
+
+/// Runs random sequences of contract calls against the [`MockedBlockchain`] and checks
+/// invariants after every call.
+///
+/// Each call has a random predecessor from [`accounts`](PropertyTest::accounts), a random
+/// deposit from [`deposits`](PropertyTest::deposits) and an action generated by the function
+/// passed to [`new`](PropertyTest::new). Before the call, the mocked blockchain is set with the
+/// context of the call. Calls which fail are reverted, as they would be on chain.
+///
+/// When an invariant is broken, the sequence is shrunk by removing calls, setting deposits to
+/// zero, using the first account as predecessor and trying the simpler actions returned by
+/// [`shrink_action`](PropertyTest::shrink_action), as long as the same invariant is broken.
+///
+/// Generators draw random values from a [`Gen`] seeded for each run, so a failure can be
+/// reproduced with the seed reported in the [`PropertyFailure`].
+///
+/// [`MockedBlockchain`]: super::MockedBlockchain
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{catch_contract_panic, PropertyTest};
+///
+/// fn counter() -> u64 {
+///     let mut buf = [0u8; 8];
+///     env::storage_read(b"c", &mut buf);
+///     u64::from_le_bytes(buf)
+/// }
+///
+/// fn add(amount: u64) {
+///     let value = counter() + amount;
+///     if value > 100 {
+///         env::panic_str("counter limit reached");
+///     }
+///     env::storage_write(b"c", &value.to_le_bytes());
+/// }
+///
+/// PropertyTest::new(|rng| rng.range(0..50))
+///     .invariant("counter is at most 100", || counter() <= 100)
+///     .check(|| (), |amount, _context| catch_contract_panic(|| add(*amount)))
+///     .unwrap();
+/// ```
+pub struct PropertyTest<A> {
+    context: VMContext,
+    accounts: Vec<String>,
+    deposits: Vec<Balance>,
+    runs: u64,
+    steps: usize,
+    seed: u64,
+    generate: Box<dyn Fn(&mut Gen) -> A>,
+    shrink: Option<Shrink<A>>,
+    invariants: Vec<Invariant>,
+}
+
+impl<A: Clone> PropertyTest<A> {
+    /// Creates a test generating actions with `generate`. By default, 100 runs of 20 calls are
+    /// made from the accounts `alice`, `bob` and `carol` without deposits.
+    pub fn new<F>(generate: F) -> Self
+    where
+        F: Fn(&mut Gen) -> A + 'static,
+    {
+        Self {
+            context: VmContextBuilder::new().build(),
+            accounts: vec!["alice".into(), "bob".into(), "carol".into()],
+            deposits: vec![0],
+            runs: 100,
+            steps: 20,
+            seed: 0,
+            generate: Box::new(generate),
+            shrink: None,
+            invariants: Vec::new(),
+        }
+    }
+
+    /// Sets the context the calls are based on.
+    pub fn context(&mut self, context: VMContext) -> &mut Self {
+        self.context = context;
+        self
+    }
+
+    /// Sets the accounts to pick the predecessor of each call from. The first account is
+    /// preferred when shrinking.
+    pub fn accounts(&mut self, accounts: &[&str]) -> &mut Self {
+        self.accounts = accounts.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// Sets the deposits to pick the deposit of each call from.
+    pub fn deposits(&mut self, deposits: &[Balance]) -> &mut Self {
+        self.deposits = deposits.to_vec();
+        self
+    }
+
+    /// Sets the number of sequences to run.
+    pub fn runs(&mut self, runs: u64) -> &mut Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Sets the number of calls of each sequence.
+    pub fn steps(&mut self, steps: usize) -> &mut Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Sets the seed of the first run. Each following run increments the seed.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets a function returning simpler versions of an action to try when shrinking. Actions
+    /// returned have to be strictly simpler, for shrinking to terminate.
+    pub fn shrink_action<F>(&mut self, shrink: F) -> &mut Self
+    where
+        F: Fn(&A) -> Vec<A> + 'static,
+    {
+        self.shrink = Some(Box::new(shrink));
+        self
+    }
+
+    /// Adds an invariant which has to hold after the setup and after every call.
+    pub fn invariant<F>(&mut self, name: &'static str, invariant: F) -> &mut Self
+    where
+        F: Fn() -> bool + 'static,
+    {
+        self.invariants.push((name, Box::new(invariant)));
+        self
+    }
+
+    /// Runs the sequences, returning the shrunk sequence of the first run that breaks an
+    /// invariant.
+    ///
+    /// Each sequence starts from clean storage and calls `setup`, which can initialize the
+    /// contract. `execute` is called with the action and context of each call and returns
+    /// whether the call succeeded. Native contract code should be called through
+    /// [`catch_contract_panic`](super::catch_contract_panic), while
+    /// [`WasmContract`](super::WasmContract)s should be called with the given context.
+    pub fn check<S, F, E>(&self, mut setup: S, mut execute: F) -> Result<(), PropertyFailure<A>>
+    where
+        S: FnMut(),
+        F: FnMut(&A, VMContext) -> Result<(), E>,
+    {
+        for run in 0..self.runs {
+            let seed = self.seed.wrapping_add(run);
+            let mut rng = Gen::new(seed);
+            let calls: Vec<_> = (0..self.steps)
+                .map(|_| self.generate_call(&mut rng))
+                .collect();
+            if let Some((len, invariant)) = self.replay(&calls, &mut setup, &mut execute) {
+                let calls = self.shrink(calls[..len].to_vec(), invariant, &mut setup, &mut execute);
+                return Err(PropertyFailure {
+                    seed,
+                    invariant,
+                    calls,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_call(&self, rng: &mut Gen) -> Call<A> {
+        Call {
+            predecessor: self.accounts[rng.index(self.accounts.len())].clone(),
+            deposit: self.deposits[rng.index(self.deposits.len())],
+            action: (self.generate)(rng),
+        }
+    }
+
+    fn broken_invariant(&self) -> Option<&'static str> {
+        self.invariants
+            .iter()
+            .find(|(_, invariant)| !invariant())
+            .map(|(name, _)| *name)
+    }
+
+    /// Runs `calls` from clean storage, returning the number of calls made until an invariant
+    /// was broken and the name of the invariant.
+    fn replay<S, F, E>(
+        &self,
+        calls: &[Call<A>],
+        setup: &mut S,
+        execute: &mut F,
+    ) -> Option<(usize, &'static str)>
+    where
+        S: FnMut(),
+        F: FnMut(&A, VMContext) -> Result<(), E>,
+    {
+        let mut env = TestEnv::new();
+        env.context(self.context.clone()).with_clean_storage().set();
+        setup();
+        if let Some(invariant) = self.broken_invariant() {
+            return Some((0, invariant));
+        }
+        for (i, call) in calls.iter().enumerate() {
+            let mut context = self.context.clone();
+            context.signer_account_id = call.predecessor.clone();
+            context.predecessor_account_id = call.predecessor.clone();
+            context.attached_deposit = call.deposit;
+            env.context(context.clone()).set();
+
+            let snapshot = with_mocked_blockchain(|b| b.snapshot());
+            if execute(&call.action, context).is_err() {
+                with_mocked_blockchain(|b| b.restore(&snapshot));
+            }
+            if let Some(invariant) = self.broken_invariant() {
+                return Some((i + 1, invariant));
+            }
+        }
+        None
+    }
+
+    /// Returns the number of calls until `invariant` is broken by `calls`, if it is.
+    fn reproduces<S, F, E>(
+        &self,
+        calls: &[Call<A>],
+        invariant: &'static str,
+        setup: &mut S,
+        execute: &mut F,
+    ) -> Option<usize>
+    where
+        S: FnMut(),
+        F: FnMut(&A, VMContext) -> Result<(), E>,
+    {
+        match self.replay(calls, setup, execute) {
+            Some((len, broken)) if broken == invariant => Some(len),
+            _ => None,
+        }
+    }
+
+    fn simplify(&self, call: &Call<A>) -> Vec<Call<A>> {
+        let mut candidates = Vec::new();
+        if call.deposit != 0 {
+            candidates.push(Call {
+                deposit: 0,
+                ..call.clone()
+            });
+        }
+        if call.predecessor != self.accounts[0] {
+            candidates.push(Call {
+                predecessor: self.accounts[0].clone(),
+                ..call.clone()
+            });
+        }
+        if let Some(shrink) = &self.shrink {
+            candidates.extend(shrink(&call.action).into_iter().map(|action| Call {
+                action,
+                ..call.clone()
+            }));
+        }
+        candidates
+    }
+
+    fn shrink<S, F, E>(
+        &self,
+        mut calls: Vec<Call<A>>,
+        invariant: &'static str,
+        setup: &mut S,
+        execute: &mut F,
+    ) -> Vec<Call<A>>
+    where
+        S: FnMut(),
+        F: FnMut(&A, VMContext) -> Result<(), E>,
+    {
+        loop {
+            let mut shrunk = false;
+
+            // Remove chunks of calls, halving the size of the chunks down to single calls.
+            let mut size = calls.len() / 2;
+            while size > 0 {
+                let mut start = 0;
+                while start < calls.len() {
+                    let mut candidate = calls.clone();
+                    candidate.drain(start..calls.len().min(start + size));
+                    match self.reproduces(&candidate, invariant, setup, execute) {
+                        Some(len) => {
+                            candidate.truncate(len);
+                            calls = candidate;
+                            shrunk = true;
+                        }
+                        None => start += size,
+                    }
+                }
+                size /= 2;
+            }
+            // Simplify the remaining calls one at a time.
+            let mut i = 0;
+            while i < calls.len() {
+                for call in self.simplify(&calls[i]) {
+                    let mut candidate = calls.clone();
+                    candidate[i] = call;
+                    if let Some(len) = self.reproduces(&candidate, invariant, setup, execute) {
+                        candidate.truncate(len);
+                        calls = candidate;
+                        shrunk = true;
+                        break;
+                    }
+                }
+                i += 1;
+            }
+
+            if !shrunk {
+                return calls;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+    use crate::mock::catch_contract_panic;
+
+    const ACCOUNTS: [&str; 3] = ["alice", "bob", "carol"];
+
+    fn balance(account_id: &str) -> u64 {
+        let mut buf = [0u8; 8];
+        env::storage_read(account_id.as_bytes(), &mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn set_balance(account_id: &str, balance: u64) {
+        env::storage_write(account_id.as_bytes(), &balance.to_le_bytes());
+    }
+
+    fn total_supply() -> u64 {
+        ACCOUNTS.iter().map(|a| balance(a)).sum()
+    }
+
+    /// Transfer which mints tokens on transfers to self, because the balance of the receiver is
+    /// read before the balance of the sender is written.
+    fn transfer(receiver: &str, amount: u64) {
+        let sender = env::predecessor_account_id();
+        let sender_balance = balance(&sender);
+        let receiver_balance = balance(receiver);
+        if sender_balance < amount {
+            env::panic_str("not enough balance");
+        }
+        set_balance(&sender, sender_balance - amount);
+        set_balance(receiver, receiver_balance + amount);
+    }
+
+    fn token_test() -> PropertyTest<(usize, u64)> {
+        let mut test = PropertyTest::new(|rng| (rng.index(3), rng.range(0..50)));
+        test.accounts(&ACCOUNTS)
+            .deposits(&[0, 1])
+            .shrink_action(|&(receiver, amount)| {
+                let mut simpler = Vec::new();
+                if receiver != 0 {
+                    simpler.push((0, amount));
+                }
+                if amount > 1 {
+                    simpler.push((receiver, amount / 2));
+                }
+                simpler
+            })
+            .invariant("supply is conserved", || total_supply() == 100);
+        test
+    }
+
+    #[test]
+    fn shrinks_to_minimal_failure() {
+        let failure = token_test()
+            .check(
+                || set_balance("alice", 100),
+                |&(receiver, amount), _| {
+                    catch_contract_panic(|| transfer(ACCOUNTS[receiver], amount))
+                },
+            )
+            .unwrap_err();
+        assert_eq!(failure.invariant, "supply is conserved");
+        assert_eq!(
+            failure.calls,
+            vec![Call {
+                predecessor: "alice".to_string(),
+                deposit: 0,
+                action: (0, 1),
+            }]
+        );
+        assert!(failure
+            .to_string()
+            .ends_with("after 1 calls\n1. alice (deposit 0): (0, 1)"));
+    }
+
+    #[test]
+    fn failed_calls_are_reverted() {
+        // Sender balance is written before the transfer fails for insufficient balance.
+        let result = token_test().check(
+            || set_balance("alice", 100),
+            |&(receiver, amount), _| {
+                catch_contract_panic(|| {
+                    let sender = env::predecessor_account_id();
+                    if sender == ACCOUNTS[receiver] {
+                        return;
+                    }
+                    let sender_balance = balance(&sender);
+                    set_balance(&sender, sender_balance.wrapping_sub(amount));
+                    if sender_balance < amount {
+                        env::panic_str("not enough balance");
+                    }
+                    let receiver = ACCOUNTS[receiver];
+                    set_balance(receiver, balance(receiver) + amount);
+                })
+            },
+        );
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -0,0 +1,531 @@
+use super::{MockedBlockchain, PromiseResult, Receipt, VmAction};
+use crate::{Balance, Gas};
+use near_vm_logic::types::ReturnData;
+use near_vm_logic::VMContext;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type AccountId = String;
+
+/// Native contract function, which reads its input and writes its result through `env`, the same
+/// way an exported wasm function does.
+pub type ContractFn = fn();
+
+/// State of an account within the [`Simulator`].
+#[derive(Clone, Debug, Default)]
+pub struct SimAccount {
+    /// Balance of the account, which is updated by deposits and transfers.
+    pub balance: Balance,
+    /// Storage of the contract deployed to the account.
+    pub storage: HashMap<Vec<u8>, Vec<u8>>,
+    /// Storage usage reported by the runtime for the account.
+    pub storage_usage: u64,
+    /// Logs emitted by executions on this account, in order.
+    pub logs: Vec<String>,
+    methods: HashMap<String, ContractFn>,
+}
+
+/// Final status of a receipt executed by the [`Simulator`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SimStatus {
+    /// Receipt succeeded with the returned value.
+    Success(Vec<u8>),
+    /// Receipt failed, and its state changes were reverted.
+    Failure,
+}
+
+/// Outcome of a call executed by the [`Simulator`], including all receipts it created.
+#[derive(Clone, Debug)]
+pub struct SimOutcome {
+    /// Status of the call, following any promise returned by the called method.
+    pub status: SimStatus,
+    /// Logs emitted by all receipts, in execution order.
+    pub logs: Vec<String>,
+    /// Gas burnt by all receipts.
+    pub gas_burnt: Gas,
+    /// Number of receipts which failed.
+    pub failures: usize,
+}
+
+impl SimOutcome {
+    /// Returns `true` if the call succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, SimStatus::Success(_))
+    }
+
+    /// Returns the value returned by the call, if it succeeded.
+    pub fn value(&self) -> Option<&[u8]> {
+        match &self.status {
+            SimStatus::Success(v) => Some(v),
+            SimStatus::Failure => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Resolution {
+    Done(SimStatus),
+    /// Result is the result of another receipt, which was returned through `promise_return`.
+    Forward(usize),
+}
+
+struct PendingReceipt {
+    id: usize,
+    predecessor_id: AccountId,
+    signer_id: AccountId,
+    receiver_id: AccountId,
+    dependencies: Vec<usize>,
+    actions: Vec<VmAction>,
+}
+
+/// Local multi-contract simulator which executes receipts created by contracts. Native Rust
+/// contract functions are registered per account, and receipts created through promises are routed
+/// to them with the right predecessor, attached deposit and gas. Promise results are passed to
+/// callbacks once the promises they depend on have been executed.
+///
+/// Each account has its own storage, balance and logs. Receipts are executed atomically, so
+/// storage and balance changes of a failed receipt are reverted, and its deposits are refunded to
+/// the predecessor. Gas is not charged to account balances, and deploying code is not supported
+/// since contracts are native functions.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{SimStatus, Simulator};
+///
+/// fn ping() {
+///     let p = env::promise_create("pong.near", "pong", &[], 0, 20_000_000_000_000);
+///     env::promise_return(p);
+/// }
+///
+/// fn pong() {
+///     env::value_return(b"pong");
+/// }
+///
+/// let mut sim = Simulator::new();
+/// sim.deploy("ping.near", &[("ping", ping)]);
+/// sim.deploy("pong.near", &[("pong", pong)]);
+///
+/// let outcome = sim.call("alice.near", "ping.near", "ping", &[], 0);
+/// assert_eq!(outcome.status, SimStatus::Success(b"pong".to_vec()));
+/// ```
+pub struct Simulator {
+    accounts: HashMap<AccountId, SimAccount>,
+    results: Vec<Option<Resolution>>,
+    /// Index of the current block, which is incremented for each executed receipt.
+    pub block_index: u64,
+    /// Timestamp of the current block in nanoseconds, which is advanced by one second for each
+    /// executed receipt.
+    pub block_timestamp: u64,
+    /// Gas attached to calls made through [`Simulator::call`].
+    pub prepaid_gas: Gas,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Default balance of accounts created implicitly by the simulator.
+const DEFAULT_BALANCE: Balance = 100 * 10u128.pow(24);
+
+impl Simulator {
+    /// Creates an empty simulator.
+    pub fn new() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            results: Vec::new(),
+            block_index: 0,
+            block_timestamp: 0,
+            prepaid_gas: 300 * 10u64.pow(12),
+        }
+    }
+
+    /// Creates an account with the given balance, or updates the balance if it exists.
+    pub fn create_account(&mut self, account_id: &str, balance: Balance) -> &mut SimAccount {
+        let account = self.accounts.entry(account_id.to_string()).or_default();
+        account.balance = balance;
+        account
+    }
+
+    /// Registers the native contract functions for an account by method name. The account is
+    /// created if it does not exist.
+    pub fn deploy(&mut self, account_id: &str, methods: &[(&str, ContractFn)]) {
+        let account = self.account_or_default(account_id);
+        account.methods = methods
+            .iter()
+            .map(|(name, f)| (name.to_string(), *f))
+            .collect();
+    }
+
+    fn account_or_default(&mut self, account_id: &str) -> &mut SimAccount {
+        self.accounts
+            .entry(account_id.to_string())
+            .or_insert_with(|| SimAccount {
+                balance: DEFAULT_BALANCE,
+                ..Default::default()
+            })
+    }
+
+    /// Returns the state of an account.
+    pub fn account(&self, account_id: &str) -> Option<&SimAccount> {
+        self.accounts.get(account_id)
+    }
+
+    /// Returns the mutable state of an account.
+    pub fn account_mut(&mut self, account_id: &str) -> Option<&mut SimAccount> {
+        self.accounts.get_mut(account_id)
+    }
+
+    /// Calls `method` on `receiver_id` as `signer_id`, then executes all receipts created by the
+    /// call until none are left. The signer is created if it does not exist, and its balance is
+    /// charged the deposit.
+    pub fn call(
+        &mut self,
+        signer_id: &str,
+        receiver_id: &str,
+        method: &str,
+        args: &[u8],
+        deposit: Balance,
+    ) -> SimOutcome {
+        let signer = self.account_or_default(signer_id);
+        signer.balance = signer
+            .balance
+            .checked_sub(deposit)
+            .expect("signer balance is less than the deposit");
+
+        let root = self.results.len();
+        self.results.push(None);
+        let mut queue = VecDeque::new();
+        queue.push_back(PendingReceipt {
+            id: root,
+            predecessor_id: signer_id.to_string(),
+            signer_id: signer_id.to_string(),
+            receiver_id: receiver_id.to_string(),
+            dependencies: vec![],
+            actions: vec![VmAction::FunctionCall {
+                method_name: method.to_string(),
+                args: args.to_vec(),
+                gas: self.prepaid_gas,
+                deposit,
+            }],
+        });
+
+        // Keep the mocked blockchain of the test, which is replaced for each execution.
+        let previous = super::with_mocked_blockchain(std::mem::take);
+
+        let mut outcome = SimOutcome {
+            status: SimStatus::Failure,
+            logs: vec![],
+            gas_burnt: 0,
+            failures: 0,
+        };
+        while let Some(receipt) = queue.pop_front() {
+            // Receipts wait for the promises they depend on.
+            let results: Option<Vec<SimStatus>> = receipt
+                .dependencies
+                .iter()
+                .map(|&id| self.resolved(id))
+                .collect();
+            match results {
+                Some(results) => self.execute(receipt, results, &mut queue, &mut outcome),
+                None if queue.is_empty() => panic!("receipt dependencies can never resolve"),
+                None => queue.push_back(receipt),
+            }
+        }
+        super::set_mocked_blockchain(previous);
+
+        outcome.status = self
+            .resolved(root)
+            .expect("all receipts have been executed");
+        outcome
+    }
+
+    /// Returns the final status of a receipt, following forwarded results, or `None` if it has
+    /// not been resolved yet.
+    fn resolved(&self, mut id: usize) -> Option<SimStatus> {
+        loop {
+            match self.results[id].as_ref()? {
+                Resolution::Done(status) => return Some(status.clone()),
+                Resolution::Forward(next) => id = *next,
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        receipt: PendingReceipt,
+        promise_results: Vec<SimStatus>,
+        queue: &mut VecDeque<PendingReceipt>,
+        outcome: &mut SimOutcome,
+    ) {
+        self.block_index += 1;
+        self.block_timestamp += 1_000_000_000;
+
+        let snapshot = self
+            .accounts
+            .get(&receipt.receiver_id)
+            .map(|a| (a.balance, a.storage.clone(), a.storage_usage));
+        let refund: Balance = receipt
+            .actions
+            .iter()
+            .map(|a| match a {
+                VmAction::Transfer { deposit } | VmAction::FunctionCall { deposit, .. } => *deposit,
+                _ => 0,
+            })
+            .sum();
+        let mut resolution = Resolution::Done(SimStatus::Success(vec![]));
+        let mut created = Vec::new();
+        let mut failed = false;
+
+        for action in &receipt.actions {
+            match action {
+                VmAction::CreateAccount => {
+                    self.accounts
+                        .entry(receipt.receiver_id.clone())
+                        .or_default();
+                }
+                VmAction::Transfer { deposit } => {
+                    match self.accounts.get_mut(&receipt.receiver_id) {
+                        Some(account) => account.balance += deposit,
+                        None => failed = true,
+                    }
+                }
+                VmAction::FunctionCall {
+                    method_name,
+                    args,
+                    gas,
+                    deposit,
+                } => {
+                    match self.function_call(
+                        &receipt,
+                        method_name,
+                        args,
+                        *gas,
+                        *deposit,
+                        &promise_results,
+                        outcome,
+                    ) {
+                        Some((return_data, receipts)) => {
+                            let offset = self.results.len() + created.len();
+                            resolution = match return_data {
+                                ReturnData::Value(v) => Resolution::Done(SimStatus::Success(v)),
+                                ReturnData::ReceiptIndex(i) => {
+                                    Resolution::Forward(offset + i as usize)
+                                }
+                                ReturnData::None => Resolution::Done(SimStatus::Success(vec![])),
+                            };
+                            created.extend(receipts.into_iter().map(|r| (offset, r)));
+                        }
+                        None => failed = true,
+                    }
+                }
+                // Other actions don't affect the simulated state.
+                _ => (),
+            }
+            if failed {
+                break;
+            }
+        }
+
+        if failed {
+            // Revert the receiver's state and refund deposits to the predecessor. Logs are kept.
+            match snapshot {
+                Some((balance, storage, storage_usage)) => {
+                    if let Some(account) = self.accounts.get_mut(&receipt.receiver_id) {
+                        account.balance = balance;
+                        account.storage = storage;
+                        account.storage_usage = storage_usage;
+                    }
+                }
+                None => {
+                    self.accounts.remove(&receipt.receiver_id);
+                }
+            }
+            if let Some(predecessor) = self.accounts.get_mut(&receipt.predecessor_id) {
+                predecessor.balance += refund;
+            }
+            outcome.failures += 1;
+            self.results[receipt.id] = Some(Resolution::Done(SimStatus::Failure));
+            return;
+        }
+
+        let base = self.results.len();
+        self.results
+            .extend(std::iter::repeat_with(|| None).take(created.len()));
+        for (i, (offset, r)) in created.into_iter().enumerate() {
+            queue.push_back(PendingReceipt {
+                id: base + i,
+                predecessor_id: receipt.receiver_id.clone(),
+                signer_id: receipt.signer_id.clone(),
+                receiver_id: r.receiver_id,
+                dependencies: r
+                    .receipt_indices
+                    .iter()
+                    .map(|&idx| offset + idx as usize)
+                    .collect(),
+                actions: r.actions,
+            });
+        }
+        self.results[receipt.id] = Some(resolution);
+    }
+
+    /// Executes a function call on the receiver of the receipt. Returns `None` if the call failed.
+    #[allow(clippy::too_many_arguments)]
+    fn function_call(
+        &mut self,
+        receipt: &PendingReceipt,
+        method_name: &str,
+        args: &[u8],
+        gas: Gas,
+        deposit: Balance,
+        promise_results: &[SimStatus],
+        outcome: &mut SimOutcome,
+    ) -> Option<(ReturnData, Vec<Receipt>)> {
+        let account = self.accounts.get_mut(&receipt.receiver_id)?;
+        let f = *account.methods.get(method_name)?;
+
+        let context = VMContext {
+            current_account_id: receipt.receiver_id.clone(),
+            signer_account_id: receipt.signer_id.clone(),
+            signer_account_pk: vec![0u8; 32],
+            predecessor_account_id: receipt.predecessor_id.clone(),
+            input: args.to_vec(),
+            block_index: self.block_index,
+            block_timestamp: self.block_timestamp,
+            epoch_height: 0,
+            // The attached deposit is added to the balance by the runtime logic.
+            account_balance: account.balance,
+            account_locked_balance: 0,
+            storage_usage: account.storage_usage,
+            attached_deposit: deposit,
+            prepaid_gas: gas,
+            random_seed: vec![0u8; 32],
+            is_view: false,
+            output_data_receivers: vec![],
+        };
+        super::set_mocked_blockchain(MockedBlockchain::new(
+            context,
+            Default::default(),
+            Default::default(),
+            promise_results
+                .iter()
+                .map(|status| match status {
+                    SimStatus::Success(v) => PromiseResult::Successful(v.clone()),
+                    SimStatus::Failure => PromiseResult::Failed,
+                })
+                .collect(),
+            std::mem::take(&mut account.storage),
+            Default::default(),
+            None,
+        ));
+
+        let succeeded = catch_unwind(AssertUnwindSafe(f)).is_ok();
+
+        let (vm_outcome, receipts, storage) = super::with_mocked_blockchain(|b| {
+            (
+                b.outcome(),
+                b.created_receipts().to_vec(),
+                std::mem::take(b.storage_mut()),
+            )
+        });
+        outcome.logs.extend(vm_outcome.logs.iter().cloned());
+        outcome.gas_burnt += vm_outcome.burnt_gas;
+        account.logs.extend(vm_outcome.logs);
+        account.storage = storage;
+
+        if !succeeded {
+            return None;
+        }
+        account.balance = vm_outcome.balance;
+        account.storage_usage = vm_outcome.storage_usage;
+        Some((vm_outcome.return_data, receipts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{callback, env};
+
+    const TGAS: Gas = 10u64.pow(12);
+
+    fn transfer_call() {
+        let p = env::promise_create("bank", "deposit", &[], 10, 20 * TGAS);
+        env::promise_then(p, "caller", "resolve", &[], 0, 20 * TGAS);
+    }
+
+    fn resolve() {
+        callback::assert_private();
+        match callback::result::<u64>(0) {
+            Some(v) => env::log_parts(&["resolved ".into(), v.into()]),
+            None => env::log_str("rolled back"),
+        }
+    }
+
+    fn deposit() {
+        env::storage_write(b"total", &env::attached_deposit().to_le_bytes());
+        env::value_return(&7u64.to_le_bytes());
+    }
+
+    fn failing_deposit() {
+        env::storage_write(b"total", &[1]);
+        env::abort();
+    }
+
+    #[test]
+    fn callback_receives_result() {
+        let mut sim = Simulator::new();
+        sim.deploy(
+            "caller",
+            &[("transfer_call", transfer_call), ("resolve", resolve)],
+        );
+        sim.deploy("bank", &[("deposit", deposit)]);
+        let caller_balance = sim.account("caller").unwrap().balance;
+
+        let outcome = sim.call("alice", "caller", "transfer_call", &[], 0);
+        assert!(outcome.is_success());
+        assert_eq!(outcome.logs, vec!["resolved 7".to_string()]);
+        assert_eq!(outcome.failures, 0);
+        assert!(outcome.gas_burnt > 0);
+
+        let bank = sim.account("bank").unwrap();
+        assert_eq!(bank.storage[&b"total".to_vec()], 10u128.to_le_bytes());
+        assert_eq!(bank.balance, DEFAULT_BALANCE + 10);
+        assert_eq!(sim.account("caller").unwrap().balance, caller_balance - 10);
+        assert_eq!(sim.account("caller").unwrap().logs, vec!["resolved 7"]);
+    }
+
+    #[test]
+    fn failed_receipt_is_reverted() {
+        let mut sim = Simulator::new();
+        sim.deploy(
+            "caller",
+            &[("transfer_call", transfer_call), ("resolve", resolve)],
+        );
+        sim.deploy("bank", &[("deposit", failing_deposit)]);
+        let caller_balance = sim.account("caller").unwrap().balance;
+
+        let outcome = sim.call("alice", "caller", "transfer_call", &[], 0);
+        assert_eq!(outcome.logs, vec!["rolled back".to_string()]);
+        assert_eq!(outcome.failures, 1);
+
+        let bank = sim.account("bank").unwrap();
+        assert!(bank.storage.is_empty());
+        assert_eq!(bank.balance, DEFAULT_BALANCE);
+        // Deposit is refunded to the predecessor.
+        assert_eq!(sim.account("caller").unwrap().balance, caller_balance);
+    }
+
+    #[test]
+    fn private_callback_rejects_external_call() {
+        let mut sim = Simulator::new();
+        sim.deploy("caller", &[("resolve", resolve)]);
+        sim.create_account("alice", 100);
+
+        let outcome = sim.call("alice", "caller", "resolve", &[], 50);
+        assert_eq!(outcome.status, SimStatus::Failure);
+        assert_eq!(sim.account("alice").unwrap().balance, 100);
+    }
+}
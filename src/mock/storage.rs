@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Copy of the storage of the [`MockedBlockchain`](super::MockedBlockchain), which can be restored
+/// or compared against later.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageSnapshot {
+    storage: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl StorageSnapshot {
+    pub(super) fn new(storage: HashMap<Vec<u8>, Vec<u8>>) -> Self {
+        Self { storage }
+    }
+
+    pub(super) fn storage(&self) -> &HashMap<Vec<u8>, Vec<u8>> {
+        &self.storage
+    }
+
+    /// Returns the value of `key` when the snapshot was taken.
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.storage.get(key).map(Vec::as_slice)
+    }
+
+    /// Returns the number of keys in the snapshot.
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Returns `true` if the storage was empty when the snapshot was taken.
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+}
+
+/// Change of the value of a single storage key in a [`StorageDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageChange {
+    /// Key was added with the value.
+    Added(Vec<u8>),
+    /// Value of the key was changed.
+    Modified {
+        /// Value in the snapshot.
+        old: Vec<u8>,
+        /// Current value.
+        new: Vec<u8>,
+    },
+    /// Key with the value was removed.
+    Removed(Vec<u8>),
+}
+
+/// Keys which changed since a [`StorageSnapshot`] was taken, sorted by key. Keys which were
+/// written with the same value are not included.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{with_mocked_blockchain, StorageChange};
+///
+/// env::storage_write(b"a", b"1");
+/// env::storage_write(b"b", b"2");
+/// let snapshot = with_mocked_blockchain(|b| b.snapshot());
+///
+/// env::storage_write(b"a", b"3");
+/// env::storage_remove(b"b");
+/// env::storage_write(b"c", b"4");
+///
+/// let diff = with_mocked_blockchain(|b| b.diff(&snapshot));
+/// assert_eq!(diff.added(), vec![&b"c"[..]]);
+/// assert_eq!(diff.modified(), vec![&b"a"[..]]);
+/// assert_eq!(diff.removed(), vec![&b"b"[..]]);
+/// assert_eq!(diff.get(b"c"), Some(&StorageChange::Added(b"4".to_vec())));
+///
+/// // Try another branch from the same state.
+/// with_mocked_blockchain(|b| b.restore(&snapshot));
+/// assert!(with_mocked_blockchain(|b| b.diff(&snapshot)).is_empty());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageDiff {
+    changes: BTreeMap<Vec<u8>, StorageChange>,
+}
+
+impl StorageDiff {
+    pub(super) fn new(
+        snapshot: &HashMap<Vec<u8>, Vec<u8>>,
+        storage: &HashMap<Vec<u8>, Vec<u8>>,
+    ) -> Self {
+        let mut changes = BTreeMap::new();
+        for (key, value) in storage {
+            match snapshot.get(key) {
+                None => {
+                    changes.insert(key.clone(), StorageChange::Added(value.clone()));
+                }
+                Some(old) if old != value => {
+                    changes.insert(
+                        key.clone(),
+                        StorageChange::Modified {
+                            old: old.clone(),
+                            new: value.clone(),
+                        },
+                    );
+                }
+                Some(_) => (),
+            }
+        }
+        for (key, value) in snapshot {
+            if !storage.contains_key(key) {
+                changes.insert(key.clone(), StorageChange::Removed(value.clone()));
+            }
+        }
+        Self { changes }
+    }
+
+    /// Returns `true` if no key changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the number of changed keys.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns the change of `key`, if it changed.
+    pub fn get(&self, key: &[u8]) -> Option<&StorageChange> {
+        self.changes.get(key)
+    }
+
+    /// Returns an iterator over the changed keys and their changes, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &StorageChange)> {
+        self.changes
+            .iter()
+            .map(|(key, change)| (key.as_slice(), change))
+    }
+
+    /// Returns the keys which were added.
+    pub fn added(&self) -> Vec<&[u8]> {
+        self.keys(|change| matches!(change, StorageChange::Added(_)))
+    }
+
+    /// Returns the keys which were modified.
+    pub fn modified(&self) -> Vec<&[u8]> {
+        self.keys(|change| matches!(change, StorageChange::Modified { .. }))
+    }
+
+    /// Returns the keys which were removed.
+    pub fn removed(&self) -> Vec<&[u8]> {
+        self.keys(|change| matches!(change, StorageChange::Removed(_)))
+    }
+
+    fn keys(&self, f: impl Fn(&StorageChange) -> bool) -> Vec<&[u8]> {
+        self.iter()
+            .filter(|(_, change)| f(change))
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Returns the changes of keys which start with `prefix`.
+    pub fn with_prefix(&self, prefix: &[u8]) -> StorageDiff {
+        StorageDiff {
+            changes: self
+                .changes
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix))
+                .map(|(key, change)| (key.clone(), change.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+    use crate::mock::{with_mocked_blockchain, VmContextBuilder};
+    use crate::testing_env;
+
+    #[test]
+    fn snapshot_diff_restore() {
+        testing_env!(VmContextBuilder::new().build());
+        with_mocked_blockchain(|b| b.storage_mut().clear());
+        env::storage_write(b"p/a", b"1");
+        env::storage_write(b"p/b", b"2");
+        env::storage_write(b"q/a", b"3");
+        let snapshot = with_mocked_blockchain(|b| b.snapshot());
+        assert_eq!(snapshot.len(), 3);
+
+        // Writing the same value is not a change.
+        env::storage_write(b"p/a", b"1");
+        env::storage_write(b"p/b", b"5");
+        env::storage_remove(b"q/a");
+        env::storage_write(b"q/b", b"6");
+
+        let diff = with_mocked_blockchain(|b| b.diff(&snapshot));
+        assert_eq!(diff.len(), 3);
+        assert_eq!(
+            diff.iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    &b"p/b"[..],
+                    &StorageChange::Modified {
+                        old: b"2".to_vec(),
+                        new: b"5".to_vec()
+                    }
+                ),
+                (&b"q/a"[..], &StorageChange::Removed(b"3".to_vec())),
+                (&b"q/b"[..], &StorageChange::Added(b"6".to_vec())),
+            ]
+        );
+        assert_eq!(diff.with_prefix(b"q/").len(), 2);
+        assert!(diff.with_prefix(b"r/").is_empty());
+
+        with_mocked_blockchain(|b| b.restore(&snapshot));
+        let mut buf = [0u8; 1];
+        assert_eq!(env::storage_read(b"q/a", &mut buf), Some(1));
+        assert_eq!(&buf, b"3");
+        assert!(!env::storage_has_key(b"q/b"));
+        assert_eq!(with_mocked_blockchain(|b| b.snapshot()), snapshot);
+    }
+}
@@ -0,0 +1,291 @@
+use super::{
+    set_mocked_blockchain, with_mocked_blockchain, CheckedMemory, MockedBlockchain,
+    VmContextBuilder,
+};
+use crate::types::Balance;
+use near_primitives_core::runtime::fees::RuntimeFeesConfig;
+use near_vm_logic::types::PromiseResult;
+use near_vm_logic::{MemoryLike, VMConfig, VMContext};
+use std::collections::HashMap;
+
+/// Default time between blocks used by [`TestEnv::advance_blocks`], in nanoseconds.
+const DEFAULT_BLOCK_TIME: u64 = 1_000_000_000;
+
+/// Builder for the testing environment, as an alternative to the positional arguments of
+/// [`testing_env!`](crate::testing_env).
+///
+/// Configuration only takes effect when the environment is [`set`](TestEnv::set). Like
+/// `testing_env!`, storage of the current [`MockedBlockchain`] is carried over unless
+/// [`with_storage`](TestEnv::with_storage) or [`with_clean_storage`](TestEnv::with_clean_storage)
+/// was called before.
+///
+/// # Example
+/// ```
+/// use nesdie::env;
+/// use nesdie::mock::{TestEnv, VmContextBuilder};
+///
+/// let mut test_env = TestEnv::new();
+/// test_env
+///     .context(VmContextBuilder::new().current_account_id("contract".into()).build())
+///     .with_clean_storage()
+///     .set();
+/// env::storage_write(b"key", b"value");
+///
+/// test_env.advance_blocks(10);
+/// assert_eq!(env::block_index(), 10);
+/// assert!(env::storage_has_key(b"key"));
+///
+/// let caller = test_env.call_as("carol", || env::predecessor_account_id());
+/// assert_eq!(caller, "carol");
+/// assert_eq!(env::predecessor_account_id(), "bob");
+/// ```
+pub struct TestEnv {
+    context: VMContext,
+    config: VMConfig,
+    fees_config: RuntimeFeesConfig,
+    validators: HashMap<String, Balance>,
+    promise_results: Vec<PromiseResult>,
+    storage: Option<HashMap<Vec<u8>, Vec<u8>>>,
+    memory: Option<CheckedMemory>,
+    block_time: u64,
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestEnv {
+    /// Creates an environment with the default context of [`VmContextBuilder`] and default
+    /// configuration.
+    pub fn new() -> Self {
+        Self {
+            context: VmContextBuilder::new().build(),
+            config: Default::default(),
+            fees_config: Default::default(),
+            validators: Default::default(),
+            promise_results: Default::default(),
+            storage: None,
+            memory: None,
+            block_time: DEFAULT_BLOCK_TIME,
+        }
+    }
+
+    /// Sets the context of the calls.
+    pub fn context(&mut self, context: VMContext) -> &mut Self {
+        self.context = context;
+        self
+    }
+
+    /// Returns the context of the calls, to change single fields.
+    pub fn context_mut(&mut self) -> &mut VMContext {
+        &mut self.context
+    }
+
+    /// Sets the configuration of the VM.
+    pub fn config(&mut self, config: VMConfig) -> &mut Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the fees for execution and storage.
+    pub fn fees_config(&mut self, fees_config: RuntimeFeesConfig) -> &mut Self {
+        self.fees_config = fees_config;
+        self
+    }
+
+    /// Sets the validators of the blockchain with their stakes.
+    pub fn validators(&mut self, validators: HashMap<String, Balance>) -> &mut Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Sets the results of the promises the calls are a callback of.
+    pub fn promise_results(&mut self, promise_results: Vec<PromiseResult>) -> &mut Self {
+        self.promise_results = promise_results;
+        self
+    }
+
+    /// Sets the time between blocks used by [`advance_blocks`](TestEnv::advance_blocks), in
+    /// nanoseconds. Defaults to one second.
+    pub fn block_time(&mut self, nanoseconds: u64) -> &mut Self {
+        self.block_time = nanoseconds;
+        self
+    }
+
+    /// Uses `memory` to check the pointers passed to host functions against the buffers registered
+    /// with it. By default pointers are trusted.
+    pub fn memory(&mut self, memory: CheckedMemory) -> &mut Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Replaces the storage with `storage` the next time the environment is set.
+    pub fn with_storage(&mut self, storage: HashMap<Vec<u8>, Vec<u8>>) -> &mut Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Clears the storage the next time the environment is set.
+    pub fn with_clean_storage(&mut self) -> &mut Self {
+        self.with_storage(HashMap::new())
+    }
+
+    /// Replaces the [`MockedBlockchain`] with one using this environment. Logs, receipts and
+    /// burnt gas of the previous instance are reset.
+    pub fn set(&mut self) {
+        let storage = match self.storage.take() {
+            Some(storage) => storage,
+            None => with_mocked_blockchain(|b| core::mem::take(b.storage_mut())),
+        };
+        set_mocked_blockchain(MockedBlockchain::new(
+            self.context.clone(),
+            self.config.clone(),
+            self.fees_config.clone(),
+            self.promise_results
+                .iter()
+                .map(clone_promise_result)
+                .collect(),
+            storage,
+            self.validators.clone(),
+            self.memory
+                .clone()
+                .map(|memory| Box::new(memory) as Box<dyn MemoryLike>),
+        ));
+    }
+
+    /// Advances the block index by `blocks` and the timestamp by as many times the block time,
+    /// then sets the environment.
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.context.block_index += blocks;
+        self.context.block_timestamp += blocks * self.block_time;
+        self.set();
+    }
+
+    /// Advances the block timestamp by `nanoseconds` without producing blocks, then sets the
+    /// environment.
+    pub fn advance_time(&mut self, nanoseconds: u64) {
+        self.context.block_timestamp += nanoseconds;
+        self.set();
+    }
+
+    /// Calls `f` with `account_id` as the signer and predecessor of the call, then sets the
+    /// environment back to the previous accounts, also if `f` panics. Logs and receipts of the
+    /// call have to be checked in `f`, as they are reset afterwards.
+    pub fn call_as<F, R>(&mut self, account_id: &str, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let signer = core::mem::replace(&mut self.context.signer_account_id, account_id.into());
+        let predecessor =
+            core::mem::replace(&mut self.context.predecessor_account_id, account_id.into());
+        let guard = RestoreAccounts {
+            env: self,
+            signer,
+            predecessor,
+        };
+        guard.env.set();
+        f()
+    }
+}
+
+/// Sets the environment back to the accounts before [`TestEnv::call_as`] when dropped, so they
+/// are restored when the call panics as well.
+struct RestoreAccounts<'a> {
+    env: &'a mut TestEnv,
+    signer: String,
+    predecessor: String,
+}
+
+impl Drop for RestoreAccounts<'_> {
+    fn drop(&mut self) {
+        self.env.context.signer_account_id = core::mem::take(&mut self.signer);
+        self.env.context.predecessor_account_id = core::mem::take(&mut self.predecessor);
+        self.env.set();
+    }
+}
+
+// `PromiseResult` doesn't implement `Clone`.
+fn clone_promise_result(result: &PromiseResult) -> PromiseResult {
+    match result {
+        PromiseResult::NotReady => PromiseResult::NotReady,
+        PromiseResult::Successful(data) => PromiseResult::Successful(data.clone()),
+        PromiseResult::Failed => PromiseResult::Failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env;
+    use crate::mock::{catch_contract_panic, ContractError};
+
+    #[test]
+    fn storage_modes() {
+        let mut test_env = TestEnv::new();
+        test_env.with_clean_storage().set();
+        env::storage_write(b"a", b"1");
+
+        // Storage is carried over by default.
+        test_env.set();
+        assert!(env::storage_has_key(b"a"));
+
+        let mut storage = HashMap::new();
+        storage.insert(b"b".to_vec(), b"2".to_vec());
+        test_env.with_storage(storage).set();
+        assert!(!env::storage_has_key(b"a"));
+        assert!(env::storage_has_key(b"b"));
+
+        test_env.with_clean_storage().set();
+        assert!(!env::storage_has_key(b"b"));
+    }
+
+    #[test]
+    fn advance_blocks_and_time() {
+        let mut test_env = TestEnv::new();
+        test_env.block_time(100).set();
+        test_env.advance_blocks(3);
+        assert_eq!(env::block_index(), 3);
+        assert_eq!(env::block_timestamp(), 300);
+
+        test_env.advance_time(50);
+        assert_eq!(env::block_index(), 3);
+        assert_eq!(env::block_timestamp(), 350);
+    }
+
+    #[test]
+    fn call_as_restores_accounts() {
+        let mut test_env = TestEnv::new();
+        test_env.context_mut().attached_deposit = 5;
+        test_env.promise_results(vec![PromiseResult::Failed]).set();
+
+        let (signer, predecessor) = test_env.call_as("carol", || {
+            assert_eq!(env::attached_deposit(), 5);
+            assert_eq!(env::promise_results_count(), 1);
+            (env::signer_account_id(), env::predecessor_account_id())
+        });
+        assert_eq!(signer, "carol");
+        assert_eq!(predecessor, "carol");
+        assert_eq!(env::signer_account_id(), "bob");
+        assert_eq!(env::predecessor_account_id(), "bob");
+    }
+
+    #[test]
+    fn call_as_restores_accounts_on_panic() {
+        let mut test_env = TestEnv::new();
+        test_env.set();
+        assert_eq!(
+            catch_contract_panic(|| test_env.call_as("carol", || env::abort())),
+            Err(ContractError::Abort)
+        );
+        assert_eq!(env::predecessor_account_id(), "bob");
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            test_env.call_as("carol", || panic!("test assertion"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(env::signer_account_id(), "bob");
+        assert_eq!(env::predecessor_account_id(), "bob");
+    }
+}
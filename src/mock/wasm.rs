@@ -0,0 +1,437 @@
+use super::MockedBlockchain;
+use near_primitives_core::runtime::fees::RuntimeFeesConfig;
+use near_vm_logic::mocks::mock_memory::MockedMemory;
+use near_vm_logic::types::PromiseResult;
+use near_vm_logic::{MemoryLike, VMConfig, VMContext, VMLogic, VMLogicError, VMOutcome};
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use wasmi::core::{HostError, Trap, TrapCode};
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store};
+
+/// Error from loading or executing a compiled contract with [`WasmContract`].
+#[derive(Debug)]
+pub enum WasmError {
+    /// The code could not be compiled or instantiated, for example because it imports a function
+    /// which is not a host function of the NEAR runtime.
+    Instantiate(String),
+    /// The code does not export a method with the name which takes no arguments.
+    MethodNotFound(String),
+    /// A host function returned an error, such as a guest panic or exceeding the prepaid gas.
+    Host(VMLogicError),
+    /// Execution trapped, for example on the `unreachable` instruction used by `env::abort`.
+    Trap(String),
+}
+
+/// Outcome of calling a method of a [`WasmContract`].
+#[derive(Debug)]
+pub struct WasmOutcome {
+    /// Outcome of the VM, including logs, the returned value and burnt gas. This is also available
+    /// on failure, with the state up to the point of the failure.
+    pub outcome: VMOutcome,
+    /// Error which stopped the execution, if any.
+    pub error: Option<WasmError>,
+}
+
+impl WasmOutcome {
+    /// Returns `true` if the call succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// [`VMLogicError`] wrapped to be returned as a trap from host functions.
+#[derive(Debug)]
+struct LogicError(VMLogicError);
+
+impl fmt::Display for LogicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl HostError for LogicError {}
+
+/// View of the linear memory of the guest, which is refreshed before each host function call since
+/// the memory can be moved when it grows. Without a view, pointers are treated as native pointers
+/// like [`MockedMemory`], so `env` functions can still be used from the test after a call.
+#[derive(Clone, Default)]
+struct GuestMemory(Rc<Cell<Option<(*mut u8, usize)>>>);
+
+impl GuestMemory {
+    fn set(&self, view: Option<(*mut u8, usize)>) {
+        self.0.set(view);
+    }
+
+    fn with_data<R>(&self, f: impl FnOnce(&mut [u8]) -> R) -> Option<R> {
+        // SAFETY: the view is only set while the guest instance is borrowed by a host function.
+        self.0
+            .get()
+            .map(|(ptr, len)| f(unsafe { std::slice::from_raw_parts_mut(ptr, len) }))
+    }
+}
+
+impl MemoryLike for GuestMemory {
+    fn fits_memory(&self, offset: u64, len: u64) -> bool {
+        self.with_data(|data| {
+            offset
+                .checked_add(len)
+                .is_some_and(|end| end <= data.len() as u64)
+        })
+        .unwrap_or_else(|| MockedMemory {}.fits_memory(offset, len))
+    }
+
+    fn read_memory(&self, offset: u64, buffer: &mut [u8]) {
+        self.with_data(|data| {
+            let offset = offset as usize;
+            buffer.copy_from_slice(&data[offset..offset + buffer.len()])
+        })
+        .unwrap_or_else(|| MockedMemory {}.read_memory(offset, buffer))
+    }
+
+    fn read_memory_u8(&self, offset: u64) -> u8 {
+        self.with_data(|data| data[offset as usize])
+            .unwrap_or_else(|| MockedMemory {}.read_memory_u8(offset))
+    }
+
+    fn write_memory(&mut self, offset: u64, buffer: &[u8]) {
+        let written = self.with_data(|data| {
+            let offset = offset as usize;
+            data[offset..offset + buffer.len()].copy_from_slice(buffer)
+        });
+        if written.is_none() {
+            MockedMemory {}.write_memory(offset, buffer)
+        }
+    }
+}
+
+struct GuestState {
+    memory: GuestMemory,
+    /// Fuel consumed by the interpreter which has already been charged as gas.
+    fuel_charged: u64,
+}
+
+/// Charges gas for `fuel` consumed by the interpreter, one regular operation per unit of fuel.
+fn charge_fuel(logic: &mut VMLogic, mut fuel: u64) -> Result<(), VMLogicError> {
+    while fuel > 0 {
+        let ops = fuel.min(u32::MAX as u64);
+        logic.gas(ops as u32)?;
+        fuel -= ops;
+    }
+    Ok(())
+}
+
+/// Charges gas for the instructions executed by the interpreter through the `gas` host function,
+/// which is how instrumented code is charged by the runtime.
+fn charge_instructions(fuel: u64) -> Result<(), VMLogicError> {
+    if fuel == 0 {
+        return Ok(());
+    }
+    super::with_mocked_blockchain(|b| b.call_host("gas", |logic| charge_fuel(logic, fuel)))
+}
+
+/// Calls the host function `name` with the logic of the mocked blockchain, after refreshing the
+/// view of the guest memory and charging the gas for the instructions executed since the last
+/// host function.
+fn with_logic<F, R>(
+    caller: &mut Caller<'_, GuestState>,
+    name: &'static str,
+    f: F,
+) -> Result<R, Trap>
+where
+    F: FnOnce(&mut VMLogic) -> Result<R, VMLogicError>,
+{
+    if let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) {
+        let data = memory.data_mut(&mut *caller);
+        let view = (data.as_mut_ptr(), data.len());
+        caller.data().memory.set(Some(view));
+    }
+    let consumed = caller.fuel_consumed().unwrap_or_default();
+    let fuel = consumed - caller.data().fuel_charged;
+    caller.data_mut().fuel_charged = consumed;
+
+    charge_instructions(fuel)
+        .and_then(|()| super::with_mocked_blockchain(|b| b.call_host(name, f)))
+        .map_err(|e| LogicError(e).into())
+}
+
+/// Defines host functions in the `env` module, which forward to the [`VMLogic`] method of the
+/// same name.
+macro_rules! host_functions {
+    ($linker:ident; $($name:ident($($arg:ident),*);)*) => {
+        $(
+            $linker
+                .func_wrap(
+                    "env",
+                    stringify!($name),
+                    |mut caller: Caller<'_, GuestState>, $($arg: u64),*| {
+                        with_logic(&mut caller, stringify!($name), |logic| logic.$name($($arg),*))
+                    },
+                )
+                .expect("host functions are only defined once");
+        )*
+    };
+}
+
+fn host_linker(engine: &Engine) -> Linker<GuestState> {
+    let mut linker = Linker::new(engine);
+    host_functions! { linker;
+        read_register(register_id, ptr);
+        register_len(register_id);
+        current_account_id(register_id);
+        signer_account_id(register_id);
+        signer_account_pk(register_id);
+        predecessor_account_id(register_id);
+        input(register_id);
+        block_index();
+        block_timestamp();
+        epoch_height();
+        storage_usage();
+        account_balance(balance_ptr);
+        account_locked_balance(balance_ptr);
+        attached_deposit(balance_ptr);
+        prepaid_gas();
+        used_gas();
+        random_seed(register_id);
+        sha256(value_len, value_ptr, register_id);
+        keccak256(value_len, value_ptr, register_id);
+        keccak512(value_len, value_ptr, register_id);
+        value_return(value_len, value_ptr);
+        panic();
+        panic_utf8(len, ptr);
+        log_utf8(len, ptr);
+        log_utf16(len, ptr);
+        promise_create(
+            account_id_len,
+            account_id_ptr,
+            method_name_len,
+            method_name_ptr,
+            arguments_len,
+            arguments_ptr,
+            amount_ptr,
+            gas
+        );
+        promise_then(
+            promise_index,
+            account_id_len,
+            account_id_ptr,
+            method_name_len,
+            method_name_ptr,
+            arguments_len,
+            arguments_ptr,
+            amount_ptr,
+            gas
+        );
+        promise_and(promise_idx_ptr, promise_idx_count);
+        promise_batch_create(account_id_len, account_id_ptr);
+        promise_batch_then(promise_index, account_id_len, account_id_ptr);
+        promise_batch_action_create_account(promise_index);
+        promise_batch_action_deploy_contract(promise_index, code_len, code_ptr);
+        promise_batch_action_function_call(
+            promise_index,
+            method_name_len,
+            method_name_ptr,
+            arguments_len,
+            arguments_ptr,
+            amount_ptr,
+            gas
+        );
+        promise_batch_action_transfer(promise_index, amount_ptr);
+        promise_batch_action_stake(promise_index, amount_ptr, public_key_len, public_key_ptr);
+        promise_batch_action_add_key_with_full_access(
+            promise_index,
+            public_key_len,
+            public_key_ptr,
+            nonce
+        );
+        promise_batch_action_add_key_with_function_call(
+            promise_index,
+            public_key_len,
+            public_key_ptr,
+            nonce,
+            allowance_ptr,
+            receiver_id_len,
+            receiver_id_ptr,
+            method_names_len,
+            method_names_ptr
+        );
+        promise_batch_action_delete_key(promise_index, public_key_len, public_key_ptr);
+        promise_batch_action_delete_account(promise_index, beneficiary_id_len, beneficiary_id_ptr);
+        promise_results_count();
+        promise_result(result_idx, register_id);
+        promise_return(promise_id);
+        storage_write(key_len, key_ptr, value_len, value_ptr, register_id);
+        storage_read(key_len, key_ptr, register_id);
+        storage_remove(key_len, key_ptr, register_id);
+        storage_has_key(key_len, key_ptr);
+        validator_stake(account_id_len, account_id_ptr, stake_ptr);
+        validator_total_stake(stake_ptr);
+    }
+    // Gas metering injected by the runtime, in case the code was already instrumented.
+    linker
+        .func_wrap(
+            "env",
+            "gas",
+            |mut caller: Caller<'_, GuestState>, ops: u32| {
+                with_logic(&mut caller, "gas", |logic| logic.gas(ops))
+            },
+        )
+        .expect("host functions are only defined once");
+    linker
+}
+
+/// Compiled wasm contract, which is executed in-process by an interpreter with host functions
+/// backed by [`VMLogic`], the same way the mocked blockchain runs native contract code. This
+/// allows testing the `.wasm` artifacts of contracts without a sandbox node.
+///
+/// Each call replaces the [`MockedBlockchain`] like `testing_env!`, keeping the existing storage
+/// and [`GasProfile`](super::GasProfile), so state is persisted between calls and can be inspected
+/// with [`with_mocked_blockchain`](super::with_mocked_blockchain) afterwards.
+///
+/// Gas for wasm instructions is charged as one regular operation per unit of fuel consumed by the
+/// interpreter, so burnt gas is close to, but not exactly, the gas burnt on chain.
+///
+/// # Example
+/// ```no_run
+/// use nesdie::mock::{VmContextBuilder, WasmContract};
+///
+/// let code = std::fs::read("res/contract.wasm").unwrap();
+/// let contract = WasmContract::new(&code).unwrap();
+///
+/// let outcome = contract.call(VmContextBuilder::new().build(), "test_function");
+/// assert!(outcome.is_success());
+/// assert_eq!(outcome.outcome.logs, vec!["test call".to_string()]);
+/// ```
+pub struct WasmContract {
+    engine: Engine,
+    module: Module,
+    linker: Linker<GuestState>,
+    config: VMConfig,
+    fees_config: RuntimeFeesConfig,
+}
+
+impl fmt::Debug for WasmContract {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WasmContract")
+            .field("config", &self.config)
+            .field("fees_config", &self.fees_config)
+            .finish()
+    }
+}
+
+impl WasmContract {
+    /// Compiles the wasm code of a contract.
+    pub fn new(code: &[u8]) -> Result<Self, WasmError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module =
+            Module::new(&engine, code).map_err(|e| WasmError::Instantiate(e.to_string()))?;
+        let linker = host_linker(&engine);
+        Ok(Self {
+            engine,
+            module,
+            linker,
+            config: Default::default(),
+            fees_config: Default::default(),
+        })
+    }
+
+    /// Sets the VM and fees configuration used for calls.
+    pub fn with_config(mut self, config: VMConfig, fees_config: RuntimeFeesConfig) -> Self {
+        self.config = config;
+        self.fees_config = fees_config;
+        self
+    }
+
+    /// Calls the exported method `method_name` with the given context. The input of the call is
+    /// the input of the context. If the call fails, storage is restored to its state before the
+    /// call.
+    pub fn call(&self, context: VMContext, method_name: &str) -> WasmOutcome {
+        self.call_with_promise_results(context, method_name, vec![])
+    }
+
+    /// Calls the exported method `method_name` with the given context and promise results, which
+    /// is used to test callbacks.
+    pub fn call_with_promise_results(
+        &self,
+        context: VMContext,
+        method_name: &str,
+        promise_results: Vec<PromiseResult>,
+    ) -> WasmOutcome {
+        let memory = GuestMemory::default();
+        let fuel = context.prepaid_gas / Into::<u64>::into(self.config.regular_op_cost.max(1)) + 1;
+        let (storage, profile) = super::with_mocked_blockchain(|b| {
+            (core::mem::take(b.storage_mut()), b.replace_profile(None))
+        });
+        // State changes of a failed call are reverted, like the changes of a failed receipt.
+        let snapshot = storage.clone();
+        let mut blockchain = MockedBlockchain::new(
+            context,
+            self.config.clone(),
+            self.fees_config.clone(),
+            promise_results,
+            storage,
+            Default::default(),
+            Some(Box::new(memory.clone())),
+        );
+        blockchain.replace_profile(profile);
+        super::set_mocked_blockchain(blockchain);
+
+        let error = self.run(&memory, method_name, fuel).err();
+        // The guest memory is dropped with the instance, so pointers are native from now on.
+        memory.set(None);
+        if error.is_some() {
+            super::with_mocked_blockchain(|b| *b.storage_mut() = snapshot);
+        }
+
+        WasmOutcome {
+            outcome: super::with_mocked_blockchain(|b| b.outcome()),
+            error,
+        }
+    }
+
+    fn run(&self, memory: &GuestMemory, method_name: &str, fuel: u64) -> Result<(), WasmError> {
+        let mut store = Store::new(
+            &self.engine,
+            GuestState {
+                memory: memory.clone(),
+                fuel_charged: 0,
+            },
+        );
+        store
+            .add_fuel(fuel)
+            .expect("fuel metering is enabled for the engine");
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| WasmError::Instantiate(e.to_string()))?;
+        let method = instance
+            .get_typed_func::<(), ()>(&store, method_name)
+            .map_err(|_| WasmError::MethodNotFound(method_name.to_string()))?;
+
+        let result = method.call(&mut store, ());
+        let trap = match result {
+            Ok(()) => None,
+            Err(trap) if trap.downcast_ref::<LogicError>().is_some() => {
+                let LogicError(e) = trap.downcast().expect("trap is a host error");
+                return Err(WasmError::Host(e));
+            }
+            Err(trap) => Some(trap),
+        };
+
+        // Charge the instructions executed after the last host function. The fuel is limited by
+        // the prepaid gas, so running out of fuel results in exceeding the prepaid gas here.
+        let consumed = match &trap {
+            Some(trap) if matches!(trap.trap_code(), Some(TrapCode::OutOfFuel)) => fuel,
+            _ => store.fuel_consumed().unwrap_or_default(),
+        };
+        let fuel = consumed - store.data().fuel_charged;
+        charge_instructions(fuel).map_err(WasmError::Host)?;
+        match trap {
+            Some(trap) => Err(WasmError::Trap(trap.to_string())),
+            None => Ok(()),
+        }
+    }
+}
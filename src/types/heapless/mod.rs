@@ -29,7 +29,6 @@ impl<const L: usize, const R: usize> Assert<L, R> {
     /// Const assert hack
     pub const LESS_EQ: usize = R - L;
 
-    /// Const assert hack
     // pub const NOT_EQ: isize = 0 / (R as isize - L as isize);
 
     /// Const assert hack
@@ -494,6 +494,22 @@ impl_from_num!(u16, 5);
 impl_from_num!(u32, 10);
 impl_from_num!(u64, 20);
 
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+impl<const N: usize> From<String<N>> for std::string::String {
+    fn from(s: String<N>) -> Self {
+        s.as_str().into()
+    }
+}
+
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+impl<const N: usize> core::convert::TryFrom<std::string::String> for String<N> {
+    type Error = ();
+
+    fn try_from(s: std::string::String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{String, Vec};
@@ -503,6 +519,18 @@ mod tests {
         static mut _S: String<8> = String::new();
     }
 
+    #[test]
+    fn std_string() {
+        use core::convert::TryFrom;
+
+        let s: String<8> = String::from("abcd");
+        assert_eq!(std::string::String::from(s), "abcd");
+
+        let s = String::<4>::try_from(std::string::String::from("abcd")).unwrap();
+        assert_eq!(s, "abcd");
+        assert!(String::<3>::try_from(std::string::String::from("abcd")).is_err());
+    }
+
     #[test]
     fn clone() {
         let s1: String<20> = String::from("abcd");
@@ -607,7 +635,7 @@ mod tests {
         let s: String<4> = String::from("ab");
         let b: Vec<u8, 4> = s.into_bytes();
         assert_eq!(b.len(), 2);
-        assert_eq!(&[b'a', b'b'], &b[..]);
+        assert_eq!(b"ab", &b[..]);
     }
 
     #[test]
@@ -1156,6 +1156,25 @@ where
     }
 }
 
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+impl<T, const N: usize> From<Vec<T, N>> for std::vec::Vec<T> {
+    fn from(v: Vec<T, N>) -> Self {
+        v.into_iter().collect()
+    }
+}
+
+#[cfg(any(feature = "std", not(target_arch = "wasm32")))]
+impl<T, const N: usize> TryFrom<std::vec::Vec<T>> for Vec<T, N> {
+    type Error = ();
+
+    fn try_from(v: std::vec::Vec<T>) -> Result<Self, Self::Error> {
+        if v.len() > N {
+            return Err(());
+        }
+        Ok(v.into_iter().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Vec;
@@ -1195,6 +1214,18 @@ mod tests {
         let mut _v: Vec<i32, 4> = Vec::new();
     }
 
+    #[test]
+    fn std_vec() {
+        use core::convert::TryFrom;
+
+        let v: Vec<i32, 4> = Vec::from_slice(&[1, 2, 3]).unwrap();
+        assert_eq!(std::vec::Vec::from(v), [1, 2, 3]);
+
+        let v = Vec::<i32, 3>::try_from(std::vec![1, 2, 3]).unwrap();
+        assert_eq!(v, [1, 2, 3]);
+        assert!(Vec::<i32, 2>::try_from(std::vec![1, 2, 3]).is_err());
+    }
+
     #[test]
     fn is_full_empty() {
         let mut v: Vec<i32, 4> = Vec::new();
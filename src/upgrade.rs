@@ -0,0 +1,173 @@
+use crate::{callback, env, gas, sys, Gas};
+
+/// Name of the method called on the new code after it has been deployed.
+pub const MIGRATE_METHOD_NAME: &str = "migrate";
+
+/// Register the new contract code is read into, to avoid copying it into guest memory.
+const CODE_REGISTER: u64 = 1;
+
+/// Aborts if the predecessor of this call is not `owner`.
+pub fn assert_predecessor_is(owner: &str) {
+    if env::predecessor_account_id() != owner {
+        env::abort();
+    }
+}
+
+/// Deploys the contract code passed as the input of the current call to the current account, and
+/// schedules a call to [`MIGRATE_METHOD_NAME`] on the new code in the same receipt. If the migration
+/// fails, the deployment is reverted with it. The result of the migration is returned as the result
+/// of the current call.
+///
+/// The migration call is attached all of the remaining gas, except for `reserved` gas which is kept
+/// for the rest of the current execution.
+///
+/// Aborts if the predecessor of this call is not `owner`, or if less than `reserved` gas remains.
+///
+/// # Example
+/// ```no_run
+/// use nesdie::{env, upgrade};
+///
+/// #[no_mangle]
+/// pub fn upgrade() {
+///     // Only allow the account's own keys to upgrade the contract.
+///     upgrade::deploy_from_input(env::current_account_id().as_str(), 5_000_000_000_000);
+/// }
+/// ```
+pub fn deploy_from_input(owner: &str, reserved: Gas) {
+    assert_predecessor_is(owner);
+
+    let current_account_id = env::current_account_id();
+    // Put input bytes into the register, which is the new wasm code.
+    unsafe { sys::input(CODE_REGISTER) };
+
+    let promise_id = env::promise_batch_create(current_account_id.as_str());
+    let promise_idx = promise_id.0;
+    //* SAFETY: Passing `u64::MAX` as the length reads the code from the register.
+    unsafe { sys::promise_batch_action_deploy_contract(promise_idx, u64::MAX, CODE_REGISTER) };
+    env::promise_batch_action_function_call(
+        promise_id,
+        MIGRATE_METHOD_NAME,
+        &[],
+        0,
+        gas::require_gas(reserved),
+    );
+    // Return the result of the migration, so that a failed upgrade fails the call.
+    env::promise_return(env::PromiseIndex(promise_idx));
+}
+
+/// Runs the migration of the contract state to `code_version`. This should be called from the
+/// [`MIGRATE_METHOD_NAME`] method of the contract.
+///
+/// `migrate` is called with the version of the state before the upgrade, where `None` means that
+/// no version was stored. The version is then updated to `code_version`. If the stored version is
+/// already `code_version`, no migration is performed.
+///
+/// Aborts if the migration was not called by the contract itself, or if the stored version is
+/// newer than `code_version`, which refuses the downgrade and reverts the deployment.
+///
+/// # Example
+/// ```no_run
+/// use nesdie::{env, upgrade};
+///
+/// #[no_mangle]
+/// pub fn migrate() {
+///     upgrade::migrate(2, |from| {
+///         if from == Some(1) {
+///             env::log_str("migrating from version 1");
+///         }
+///     });
+/// }
+/// ```
+pub fn migrate<F>(code_version: u32, migrate: F)
+where
+    F: FnOnce(Option<u32>),
+{
+    callback::assert_private();
+
+    let stored = env::state_version();
+    match stored {
+        Some(v) if v > code_version => env::abort(),
+        Some(v) if v == code_version => (),
+        _ => {
+            migrate(stored);
+            env::state_version_write(code_version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{with_mocked_blockchain, VmAction, VmContextBuilder};
+    use crate::testing_env;
+
+    fn self_call() {
+        testing_env!(VmContextBuilder::new()
+            .current_account_id("alice".into())
+            .predecessor_account_id("alice".into())
+            .build());
+    }
+
+    #[test]
+    fn deploy_schedules_migration() {
+        let code = b"\0asm".to_vec();
+        testing_env!(VmContextBuilder::new()
+            .predecessor_account_id("owner".into())
+            .input(code.clone())
+            .build());
+        deploy_from_input("owner", 5_000_000_000_000);
+
+        let receipts = with_mocked_blockchain(|b| b.created_receipts().to_vec());
+        assert_eq!(receipts[0].receiver_id, "alice");
+        assert!(
+            matches!(&receipts[0].actions[0], VmAction::DeployContract { code: c } if c == &code)
+        );
+        assert!(matches!(
+            &receipts[0].actions[1],
+            VmAction::FunctionCall { method_name, .. } if method_name == MIGRATE_METHOD_NAME
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn deploy_rejects_non_owner() {
+        testing_env!(VmContextBuilder::new()
+            .predecessor_account_id("bob".into())
+            .build());
+        deploy_from_input("owner", 0);
+    }
+
+    #[test]
+    fn migrate_updates_version() {
+        self_call();
+        let mut from = None;
+        migrate(1, |v| from = Some(v));
+        assert_eq!(from, Some(None));
+        assert_eq!(env::state_version(), Some(1));
+
+        // Migration is skipped when already at the code version.
+        migrate(1, |_| panic!("already migrated"));
+
+        migrate(2, |v| from = Some(v));
+        assert_eq!(from, Some(Some(1)));
+        assert_eq!(env::state_version(), Some(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn migrate_refuses_downgrade() {
+        self_call();
+        env::state_version_write(3);
+        migrate(2, |_| ());
+    }
+
+    #[test]
+    #[should_panic]
+    fn migrate_rejects_external_call() {
+        testing_env!(VmContextBuilder::new()
+            .current_account_id("alice".into())
+            .predecessor_account_id("bob".into())
+            .build());
+        migrate(1, |_| ());
+    }
+}
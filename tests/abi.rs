@@ -0,0 +1,70 @@
+//! Checks the ABI declared by the example contracts against the checked-in JSON description and
+//! the methods exported by the compiled contracts. Run with `UPDATE_ABI=1` to update the JSON.
+
+mod common;
+
+mod proxy {
+    include!("../examples/proxy/src/abi.rs");
+}
+
+mod smol_ft {
+    include!("../examples/smol_ft/src/abi.rs");
+}
+
+use nesdie::abi::Abi;
+use std::collections::BTreeSet;
+
+fn check_abi_json(example: &str, abi: &Abi) {
+    let path = format!("./examples/{}/abi.json", example);
+    let json = abi.to_json();
+    if std::env::var_os("UPDATE_ABI").is_some() {
+        std::fs::write(&path, &json).unwrap();
+    }
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        json,
+        "ABI of {} changed, run with UPDATE_ABI=1 to update it",
+        example
+    );
+}
+
+fn exported_functions(example: &str) -> BTreeSet<String> {
+    let code = std::fs::read(common::build_example(example)).unwrap();
+    let module = wasmi::Module::new(&wasmi::Engine::default(), &code[..]).unwrap();
+    module
+        .exports()
+        .filter(|export| export.ty().func().is_some())
+        .map(|export| export.name().to_string())
+        .collect()
+}
+
+#[test]
+fn proxy_abi() {
+    check_abi_json("proxy", &proxy::abi());
+}
+
+#[test]
+fn smol_ft_abi() {
+    check_abi_json("smol_ft", &smol_ft::abi());
+}
+
+fn check_abi_exports(example: &str, abi: &Abi) {
+    let declared: BTreeSet<_> = abi
+        .methods
+        .iter()
+        .map(|method| method.name.to_string())
+        .collect();
+    assert_eq!(declared, exported_functions(example));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn proxy_abi_matches_exports() {
+    check_abi_exports("proxy", &proxy::abi());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn smol_ft_abi_matches_exports() {
+    check_abi_exports("smol_ft", &smol_ft::abi());
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use nesdie_wasm::Module;
+
+/// Builds the example with symbols and audits it, failing with the findings if any.
+fn audit_example(example: &str, allowed_imports: &[&str]) {
+    let wasm = std::fs::read(common::build_example_with_names(example)).unwrap();
+    let module = Module::parse(&wasm).unwrap();
+    assert!(
+        !module.function_names().unwrap().is_empty(),
+        "symbols were stripped"
+    );
+
+    let audit = module.audit(allowed_imports).unwrap();
+    assert!(audit.is_clean(), "{}:\n{}", example, audit);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn proxy_audit() {
+    audit_example(
+        "proxy",
+        &[
+            "current_account_id",
+            "input",
+            "predecessor_account_id",
+            "promise_batch_action_deploy_contract",
+            "promise_batch_action_function_call",
+            "promise_batch_action_transfer",
+            "promise_batch_create",
+            "read_register",
+            "register_len",
+        ],
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn raw_contract_audit() {
+    audit_example("raw-contract", &["log_utf8"]);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fungible_token_audit() {
+    audit_example(
+        "smol_ft",
+        &[
+            "input",
+            "predecessor_account_id",
+            "read_register",
+            "register_len",
+            "sha256",
+            "storage_has_key",
+            "storage_read",
+            "storage_write",
+            "value_return",
+        ],
+    );
+}
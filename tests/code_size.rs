@@ -1,38 +1,27 @@
+mod common;
+
 /// Compiles contract to wasm with release configuration and returns the code size.
 fn check_example_size(example: &str) -> usize {
-    let status = std::process::Command::new("cargo")
-        .env("RUSTFLAGS", "-C link-arg=-s")
-        .args([
-            "build",
-            "--release",
-            "--target",
-            "wasm32-unknown-unknown",
-            "--manifest-path",
-        ])
-        .arg(format!("./examples/{}/Cargo.toml", example))
-        .status()
-        .unwrap();
-    if !status.success() {
-        panic!("building wasm example returned non-zero code {}", status);
-    }
-
-    let wasm = std::fs::read(format!(
-        "./examples/{}/target/wasm32-unknown-unknown/release/{}.wasm",
+    std::fs::read(common::build_example(example)).unwrap().len()
+}
+
+/// Compiles contract with the bump allocator instead of `wee_alloc` and returns the code size.
+fn check_example_size_with_bump_alloc(example: &str) -> usize {
+    std::fs::read(common::build_example_with_nesdie_features(
         example,
-        example.replace('-', "_")
+        &["bump-alloc"],
     ))
-    .unwrap();
-
-    wasm.len()
+    .unwrap()
+    .len()
 }
 
 #[test]
-#[ignore = "proxy can't be compiled on stable (alloc error handler)"]
+#[cfg_attr(miri, ignore)]
 fn proxy_code_size_check() {
     let size = check_example_size("proxy");
 
-    // 3535
-    assert!(size < 3600);
+    // 2564, trapping on allocation failure instead of the alloc error handler
+    assert!(size < 2700);
 }
 
 #[test]
@@ -52,3 +41,51 @@ fn fungible_token_code_size_check() {
     // 1806 in current Rust version
     assert!(size < 2000);
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn minified_fungible_token_code_size_check() {
+    let wasm = std::fs::read(common::build_example("smol_ft")).unwrap();
+    let minified = nesdie_wasm::minify(&wasm, None).unwrap();
+
+    // The minified contract is still valid.
+    wasmi::Module::new(&wasmi::Engine::default(), &minified[..]).unwrap();
+
+    // 1164 in current Rust version
+    assert!(minified.len() < wasm.len());
+    assert!(minified.len() < 1250);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn bump_alloc_proxy_code_size_check() {
+    let size = check_example_size_with_bump_alloc("proxy");
+
+    // 1580, compared to 2564 with `wee_alloc`
+    assert!(size < 1700);
+    assert!(size < check_example_size("proxy"));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn bump_alloc_fungible_token_code_size_check() {
+    // The token doesn't allocate, so no allocator is included either way.
+    assert_eq!(
+        check_example_size_with_bump_alloc("smol_ft"),
+        check_example_size("smol_ft")
+    );
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn panic_code_proxy_code_size_check() {
+    let size = std::fs::read(common::build_example_with_nesdie_features(
+        "proxy",
+        &["panic-code"],
+    ))
+    .unwrap()
+    .len();
+
+    // 2659, panics abort with `E0` instead of trapping without formatting a message
+    assert!(size < check_example_size("proxy") + 150);
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+fn build(example: &str, rustflags: &str, target_dir: &str, features: &[&str]) -> PathBuf {
+    let status = std::process::Command::new("cargo")
+        .env("RUSTFLAGS", rustflags)
+        .env("CARGO_TARGET_DIR", target_dir)
+        .args([
+            "build",
+            "--release",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--manifest-path",
+        ])
+        .arg(format!("./examples/{}/Cargo.toml", example))
+        .args(features.iter().flat_map(|feature| ["--features", feature]))
+        .status()
+        .unwrap();
+    if !status.success() {
+        panic!("building wasm example returned non-zero code {}", status);
+    }
+
+    PathBuf::from(format!(
+        "{}/wasm32-unknown-unknown/release/{}.wasm",
+        target_dir,
+        example.replace('-', "_")
+    ))
+}
+
+/// Compiles contract to wasm with release configuration and returns the path of the artifact.
+#[allow(dead_code)]
+pub fn build_example(example: &str) -> PathBuf {
+    build(
+        example,
+        "-C link-arg=-s",
+        &format!("./examples/{}/target", example),
+        &[],
+    )
+}
+
+/// Compiles contract like [`build_example`], but without stripping the symbols, so that function
+/// names are kept in the `name` section. A separate target directory is used so that the
+/// stripped artifact isn't rebuilt.
+#[allow(dead_code)]
+pub fn build_example_with_names(example: &str) -> PathBuf {
+    build(
+        example,
+        "",
+        &format!("./examples/{}/target/names", example),
+        &[],
+    )
+}
+
+/// Compiles contract like [`build_example`] with additional features of `nesdie` enabled. A
+/// separate target directory is used so that the artifact without the features isn't rebuilt.
+#[allow(dead_code)]
+pub fn build_example_with_nesdie_features(example: &str, features: &[&str]) -> PathBuf {
+    let features: Vec<_> = features.iter().map(|f| format!("nesdie/{}", f)).collect();
+    let features: Vec<_> = features.iter().map(String::as_str).collect();
+    build(
+        example,
+        "-C link-arg=-s",
+        &format!(
+            "./examples/{}/target/{}",
+            example,
+            features.join("-").replace('/', "-")
+        ),
+        &features,
+    )
+}
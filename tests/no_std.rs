@@ -0,0 +1,28 @@
+//! Macros used by a `no_std` crate, which has no `std` in its extern prelude.
+
+#![no_std]
+
+use nesdie::error::ErrorCode;
+
+nesdie::error_codes! {
+    /// Errors of a `no_std` contract.
+    pub enum ContractError {
+        /// The first error.
+        NotFound = 1,
+    }
+}
+
+nesdie::abi! {
+    /// Returns the balance of the account.
+    view fn balance(borsh(AccountId)) -> borsh(u128);
+}
+
+#[test]
+fn error_codes_in_no_std_crate() {
+    assert_eq!(ContractError::NotFound.code(), 1);
+}
+
+#[test]
+fn abi_in_no_std_crate() {
+    assert_eq!(abi().method("balance").unwrap().docs.len(), 1);
+}
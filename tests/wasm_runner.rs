@@ -0,0 +1,204 @@
+//! Runs the compiled example contracts in-process through the interpreter of the mock.
+
+mod common;
+
+use nesdie::env;
+use nesdie::mock::{
+    with_mocked_blockchain, HostError, PropertyTest, ReturnData, VMLogicError, VmContextBuilder,
+    WasmContract, WasmError,
+};
+use std::convert::TryInto;
+
+fn load_example(example: &str) -> WasmContract {
+    let code = std::fs::read(common::build_example(example)).unwrap();
+    WasmContract::new(&code).unwrap()
+}
+
+/// Input of the token contract, which is the hash of an account and an amount.
+fn ft_input(account_id: &str, amount: u64) -> Vec<u8> {
+    let mut input = env::sha256(account_id.as_bytes()).to_vec();
+    input.extend_from_slice(&amount.to_le_bytes());
+    input.extend_from_slice(&[0; 24]);
+    input
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn raw_contract_logs() {
+    let contract = load_example("raw-contract");
+
+    let outcome = contract.call(VmContextBuilder::new().build(), "test_function");
+    assert!(outcome.is_success());
+    assert_eq!(outcome.outcome.logs, vec!["test call".to_string()]);
+    assert!(outcome.outcome.burnt_gas > 0);
+
+    let outcome = contract.call(VmContextBuilder::new().build(), "missing");
+    assert!(matches!(outcome.error, Some(WasmError::MethodNotFound(_))));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fungible_token_transfers() {
+    let contract = load_example("smol_ft");
+
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .input(ft_input("alice", 1000))
+            .build(),
+        "init",
+    );
+    assert!(outcome.is_success(), "{:?}", outcome.error);
+    assert_eq!(with_mocked_blockchain(|b| b.storage_mut().len()), 2);
+
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .predecessor_account_id("alice".into())
+            .input(ft_input("bob", 300))
+            .build(),
+        "transfer",
+    );
+    assert!(outcome.is_success(), "{:?}", outcome.error);
+
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .input(env::sha256(b"bob").to_vec())
+            .build(),
+        "get_balance",
+    );
+    let mut expected = 300u64.to_le_bytes().to_vec();
+    expected.extend_from_slice(&[0; 24]);
+    assert_eq!(outcome.outcome.return_data, ReturnData::Value(expected));
+
+    // Storage written by the contract can be read natively after the call.
+    let mut balance = [0u8; 32];
+    assert_eq!(
+        env::storage_read(&env::sha256(b"alice"), &mut balance),
+        Some(32)
+    );
+    assert_eq!(balance[..8], 700u64.to_le_bytes());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fungible_token_failures() {
+    let contract = load_example("smol_ft");
+
+    // Transfer without a balance aborts the contract.
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .predecessor_account_id("carol".into())
+            .input(ft_input("bob", 1))
+            .build(),
+        "transfer",
+    );
+    assert!(
+        matches!(outcome.error, Some(WasmError::Trap(ref trap)) if trap.contains("`unreachable`"))
+    );
+
+    // Writes before a panic are reverted. The owner balance is written before the receiver
+    // balance overflows.
+    contract.call(
+        VmContextBuilder::new()
+            .input(ft_input("alice", 1000))
+            .build(),
+        "init",
+    );
+    env::storage_write(&env::sha256(b"bob"), &[0xff; 32]);
+    let storage = with_mocked_blockchain(|b| b.storage_mut().clone());
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .predecessor_account_id("alice".into())
+            .input(ft_input("bob", 1))
+            .build(),
+        "transfer",
+    );
+    assert!(outcome.error.is_some());
+    assert_eq!(with_mocked_blockchain(|b| b.storage_mut().clone()), storage);
+
+    // Execution stops once the prepaid gas is exceeded.
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .input(ft_input("alice", 1000))
+            .prepaid_gas(1)
+            .build(),
+        "init",
+    );
+    assert!(matches!(
+        outcome.error,
+        Some(WasmError::Host(VMLogicError::HostError(
+            HostError::GasExceeded
+        )))
+    ));
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fungible_token_transfer_gas() {
+    let contract = load_example("smol_ft");
+    contract.call(
+        VmContextBuilder::new()
+            .input(ft_input("alice", 1000))
+            .build(),
+        "init",
+    );
+
+    // Hashing the input natively would be included in the profile.
+    let input = ft_input("bob", 300);
+    with_mocked_blockchain(|b| b.start_profiling());
+    let outcome = contract.call(
+        VmContextBuilder::new()
+            .predecessor_account_id("alice".into())
+            .input(input)
+            .build(),
+        "transfer",
+    );
+    assert!(outcome.is_success(), "{:?}", outcome.error);
+    let profile = with_mocked_blockchain(|b| b.profile()).unwrap();
+
+    assert_eq!(profile.get("storage_write").unwrap().calls, 2);
+    assert_eq!(profile.total().gas, outcome.outcome.burnt_gas);
+    // 304062242298 gas in the current version, with headroom for changes in code generation
+    assert!(profile.total().gas < 310_000_000_000);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fungible_token_supply_is_conserved() {
+    const ACCOUNTS: [&str; 3] = ["alice", "bob", "carol"];
+    let contract = load_example("smol_ft");
+
+    let total_supply = || {
+        with_mocked_blockchain(|b| {
+            b.storage_mut()
+                .iter()
+                .filter(|(key, _)| key.len() == 32)
+                .map(|(_, balance)| u64::from_le_bytes(balance[..8].try_into().unwrap()))
+                .sum::<u64>()
+        })
+    };
+    let mut test = PropertyTest::new(|rng| (rng.index(3), rng.range(0..400)));
+    test.accounts(&ACCOUNTS)
+        .runs(10)
+        .invariant("supply is conserved", move || total_supply() == 1000);
+
+    let result = test.check(
+        || {
+            contract.call(
+                VmContextBuilder::new()
+                    .input(ft_input("alice", 1000))
+                    .build(),
+                "init",
+            );
+        },
+        |&(receiver, amount), mut context| {
+            context.input = ft_input(ACCOUNTS[receiver], amount);
+            match contract.call(context, "transfer").error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        },
+    );
+    if let Err(failure) = result {
+        panic!("{}", failure);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{Error, Import, Module};
+use std::fmt;
+
+/// Minimum length of a string in a data segment to be checked for paths.
+const MIN_STRING_LEN: usize = 4;
+
+/// Parts of function names which are pulled in by `core::fmt`.
+///
+/// Entry points of formatted panics like `core::panicking::panic_fmt` are not included, because
+/// they are kept on a stable toolchain even when the panic handler discards the message.
+const FMT_SYMBOLS: &[&str] = &["core::fmt", "core..fmt", "4core3fmt"];
+
+/// Parts of strings which are only present in paths of source files.
+const PATH_PATTERNS: &[&str] = &[
+    "/rustc/",
+    "/.cargo/",
+    "/.rustup/",
+    "/home/",
+    "/root/",
+    ":\\",
+];
+
+/// Findings of [`Module::audit`], which are all code size or privacy issues of a contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Audit {
+    /// Functions from `core::fmt`, which is never needed by a contract and is one of the largest
+    /// contributors to code size.
+    pub fmt_functions: Vec<String>,
+    /// Strings in data segments which look like paths of source files, usually from panic
+    /// locations of `unwrap`, `expect` or indexing. These leak local paths into the binary.
+    pub paths: Vec<String>,
+    /// Imports which are not in the allowlist.
+    pub disallowed_imports: Vec<Import>,
+}
+
+impl Audit {
+    /// Whether nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.fmt_functions.is_empty() && self.paths.is_empty() && self.disallowed_imports.is_empty()
+    }
+}
+
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "no issues found");
+        }
+        for function in &self.fmt_functions {
+            writeln!(f, "fmt function: {}", function)?;
+        }
+        for path in &self.paths {
+            writeln!(f, "path in data: {:?}", path)?;
+        }
+        for import in &self.disallowed_imports {
+            writeln!(f, "disallowed import: {}::{}", import.module, import.name)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_fmt_symbol(name: &str) -> bool {
+    FMT_SYMBOLS.iter().any(|symbol| name.contains(symbol))
+}
+
+fn is_path(s: &str) -> bool {
+    let rust_file = s.match_indices(".rs").any(|(i, _)| {
+        !s[i + 3..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+    });
+    rust_file || PATH_PATTERNS.iter().any(|pattern| s.contains(pattern))
+}
+
+/// Printable ASCII strings in `data` of at least [`MIN_STRING_LEN`] bytes.
+fn strings(data: &[u8]) -> impl Iterator<Item = &str> {
+    data.split(|b| !(0x20..0x7f).contains(b))
+        .filter(|s| s.len() >= MIN_STRING_LEN)
+        //* Only printable ASCII bytes are left, which is valid UTF-8.
+        .map(|s| core::str::from_utf8(s).unwrap())
+}
+
+impl Module {
+    /// Checks the module for `core::fmt` functions, paths of source files and imports which are
+    /// not in `allowed_imports`. Imports from modules other than `env` are never allowed.
+    ///
+    /// Functions are found through the `name` section, so the module has to be built without
+    /// stripping symbols (`-C link-arg=-s`) for `core::fmt` to be detected.
+    pub fn audit(&self, allowed_imports: &[&str]) -> Result<Audit, Error> {
+        let fmt_functions = self
+            .function_names()?
+            .into_iter()
+            .filter(|name| is_fmt_symbol(name))
+            .collect();
+
+        let mut paths: Vec<String> = Vec::new();
+        for data in self.data()? {
+            for s in strings(&data) {
+                if is_path(s) && !paths.iter().any(|p| p == s) {
+                    paths.push(s.to_string());
+                }
+            }
+        }
+
+        let disallowed_imports = self
+            .imports()?
+            .into_iter()
+            .filter(|import| {
+                import.module != "env" || !allowed_imports.contains(&import.name.as_str())
+            })
+            .collect();
+
+        Ok(Audit {
+            fmt_functions,
+            paths,
+            disallowed_imports,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fmt_symbols() {
+        assert!(is_fmt_symbol("core::fmt::write::h3b2c1f0a"));
+        assert!(is_fmt_symbol("_ZN4core3fmt9Formatter3pad17h0E"));
+        assert!(!is_fmt_symbol("core::panicking::panic_fmt::h1"));
+        assert!(!is_fmt_symbol("smol_ft::transfer"));
+    }
+
+    #[test]
+    fn paths() {
+        assert!(is_path("src/lib.rs"));
+        assert!(is_path(
+            "/root/.cargo/registry/src/index/near-sys-0.1.0/src/lib.rs"
+        ));
+        assert!(is_path("/rustc/59807616e1fa/library/core/src/num/mod.rs"));
+        assert!(is_path("C:\\Users\\dev\\contract"));
+        assert!(!is_path("users"));
+        assert!(!is_path("alice.near"));
+        assert!(!is_path("a.rsync"));
+
+        let data = b"\0\0src/lib.rs\x0b\0\0\0ok\0called `Option::unwrap()`";
+        let found: Vec<_> = strings(data).collect();
+        assert_eq!(found, ["src/lib.rs", "called `Option::unwrap()`"]);
+    }
+}
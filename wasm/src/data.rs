@@ -0,0 +1,232 @@
+use crate::encoding::{write_i32, write_len_bytes, write_u32, Reader};
+use crate::Error;
+
+/// Data segment of a module.
+pub(crate) struct Segment {
+    flags: u32,
+    memory: u32,
+    /// Encoded offset expression of an active segment.
+    expr: Vec<u8>,
+    /// Offset of an active segment, if it's a constant.
+    offset: Option<u32>,
+    data: Vec<u8>,
+}
+
+impl Segment {
+    fn start(&self) -> u64 {
+        self.offset.unwrap_or(0) as u64
+    }
+
+    fn end(&self) -> u64 {
+        self.start() + self.data.len() as u64
+    }
+
+    fn covers(&self, memory: u32, addr: u64) -> bool {
+        self.memory == memory && self.start() <= addr && addr < self.end()
+    }
+
+    fn overlaps(&self, other: &Segment) -> bool {
+        self.memory == other.memory && self.start() < other.end() && other.start() < self.end()
+    }
+
+    /// Whether `other` only writes bytes which `self` already writes to the same addresses.
+    fn contains(&self, other: &Segment) -> bool {
+        self.memory == other.memory
+            && self.start() <= other.start()
+            && other.end() <= self.end()
+            && self.data[(other.start() - self.start()) as usize..][..other.data.len()]
+                == other.data
+    }
+
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn set_offset(&mut self, offset: u32) {
+        self.offset = Some(offset);
+        self.expr.clear();
+        self.expr.push(0x41);
+        //* Offsets are unsigned, but encoded as an `i32.const`.
+        write_i32(&mut self.expr, offset as i32);
+        self.expr.push(0x0b);
+    }
+}
+
+pub(crate) fn parse(payload: &[u8]) -> Result<Vec<Segment>, Error> {
+    let mut reader = Reader::new(payload);
+    let count = reader.u32()?;
+    let mut segments = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let flags = reader.u32()?;
+        let (memory, expr, offset) = match flags {
+            0 | 2 => {
+                let memory = if flags == 2 { reader.u32()? } else { 0 };
+                let (expr, offset) = reader.const_expr()?;
+                (memory, expr.to_vec(), offset.map(|offset| offset as u32))
+            }
+            1 => (0, Vec::new(), None),
+            flags => return Err(Error::UnknownSegment(flags)),
+        };
+        let data = reader.len_bytes()?.to_vec();
+        segments.push(Segment {
+            flags,
+            memory,
+            expr,
+            offset,
+            data,
+        });
+    }
+    if !reader.is_empty() {
+        return Err(Error::InvalidSectionLength(crate::DATA_SECTION));
+    }
+    Ok(segments)
+}
+
+pub(crate) fn encode(segments: &[Segment]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_u32(&mut payload, segments.len() as u32);
+    for segment in segments {
+        write_u32(&mut payload, segment.flags);
+        if segment.flags == 2 {
+            write_u32(&mut payload, segment.memory);
+        }
+        payload.extend_from_slice(&segment.expr);
+        write_len_bytes(&mut payload, &segment.data);
+    }
+    payload
+}
+
+/// Whether the byte of `segments[i]` at `addr` doesn't change the initial memory, because a later
+/// segment overwrites it, or because it's zero and no earlier segment writes to it.
+fn is_redundant(segments: &[Segment], i: usize, addr: u64, byte: u8) -> bool {
+    let memory = segments[i].memory;
+    segments[i + 1..].iter().any(|s| s.covers(memory, addr))
+        || (byte == 0 && !segments[..i].iter().any(|s| s.covers(memory, addr)))
+}
+
+/// Removes segments whose bytes are already written by an earlier segment, and redundant bytes
+/// at the edges of segments. Returns `false` without changing anything if the segments can't be
+/// minimized.
+pub(crate) fn minimize(segments: &mut Vec<Segment>) -> bool {
+    if segments.iter().any(|s| s.offset.is_none()) {
+        return false;
+    }
+    let mut changed = false;
+
+    let mut j = 0;
+    while j < segments.len() {
+        let duplicate = (0..j).any(|i| {
+            segments[i].contains(&segments[j])
+                && !segments[i + 1..j].iter().any(|k| k.overlaps(&segments[j]))
+        });
+        if duplicate {
+            segments.remove(j);
+            changed = true;
+        } else {
+            j += 1;
+        }
+    }
+
+    let mut i = 0;
+    while i < segments.len() {
+        let start = segments[i].start();
+        let leading = segments[i]
+            .data
+            .iter()
+            .enumerate()
+            .take_while(|(n, &byte)| is_redundant(segments, i, start + *n as u64, byte))
+            .count();
+        let trailing = segments[i].data[leading..]
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(n, &byte)| is_redundant(segments, i, start + (leading + *n) as u64, byte))
+            .count();
+        if leading + trailing == segments[i].data.len() {
+            segments.remove(i);
+            changed = true;
+            continue;
+        }
+        if leading > 0 || trailing > 0 {
+            let segment = &mut segments[i];
+            let len = segment.data.len();
+            segment.data.truncate(len - trailing);
+            segment.data.drain(..leading);
+            segment.set_offset(start as u32 + leading as u32);
+            changed = true;
+        }
+        i += 1;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active(offset: u32, data: &[u8]) -> Segment {
+        let mut segment = Segment {
+            flags: 0,
+            memory: 0,
+            expr: Vec::new(),
+            offset: None,
+            data: data.to_vec(),
+        };
+        segment.set_offset(offset);
+        segment
+    }
+
+    fn layout(segments: &[Segment]) -> Vec<(u32, &[u8])> {
+        segments
+            .iter()
+            .map(|s| (s.offset.unwrap(), &s.data[..]))
+            .collect()
+    }
+
+    /// Initial memory produced by the segments.
+    fn image(segments: &[Segment]) -> Vec<u8> {
+        let mut memory = vec![0; 16];
+        for s in segments {
+            memory[s.start() as usize..s.end() as usize].copy_from_slice(&s.data);
+        }
+        memory
+    }
+
+    #[test]
+    fn overlapping_segments() {
+        let mut segments = vec![
+            active(0, b"abcde"),
+            active(1, b"\0"),
+            active(3, b"\0x"),
+            active(1, b"\0"),
+            active(6, b"\0\0y\0"),
+            active(12, b"z"),
+            active(12, b"z"),
+        ];
+        let memory = image(&segments);
+        assert!(minimize(&mut segments));
+        assert_eq!(image(&segments), memory);
+        // Overwritten bytes of the first segment are only trimmed at its edge, so the zero
+        // overwriting `b` has to be kept. The zero at 3 is redundant once `de` is trimmed, and
+        // the second `\0` at 1 and `z` at 12 are duplicates.
+        assert_eq!(
+            layout(&segments),
+            [
+                (0, &b"abc"[..]),
+                (1, &b"\0"[..]),
+                (4, &b"x"[..]),
+                (8, &b"y"[..]),
+                (12, &b"z"[..])
+            ]
+        );
+        assert!(!minimize(&mut segments));
+    }
+
+    #[test]
+    fn passive_segments_are_kept() {
+        let payload = [2, 1, 1, 0, 0, 0x41, 0, 0x0b, 1, 0];
+        let mut segments = parse(&payload).unwrap();
+        assert!(!minimize(&mut segments));
+        assert_eq!(encode(&segments), payload);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::Error;
+use std::convert::TryFrom;
+
+/// Cursor over the bytes of a module, reading the primitive encodings of the binary format.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    pub(crate) fn byte(&mut self) -> Result<u8, Error> {
+        let byte = *self.bytes.get(self.pos).ok_or(Error::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos.checked_add(len).ok_or(Error::UnexpectedEof)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(Error::UnexpectedEof)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        let mut result = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            if shift == 28 && byte > 0x0f {
+                return Err(Error::InvalidLeb);
+            }
+            result |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(Error::InvalidLeb)
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, Error> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            result |= ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+                break;
+            }
+            if shift >= 35 {
+                return Err(Error::InvalidLeb);
+            }
+        }
+        i32::try_from(result).map_err(|_| Error::InvalidLeb)
+    }
+
+    pub(crate) fn len_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.u32()? as usize;
+        self.bytes(len)
+    }
+
+    pub(crate) fn name(&mut self) -> Result<&'a str, Error> {
+        core::str::from_utf8(self.len_bytes()?).map_err(|_| Error::InvalidUtf8)
+    }
+
+    /// Skips the limits of a table or memory type.
+    pub(crate) fn limits(&mut self) -> Result<(), Error> {
+        let flags = self.byte()?;
+        self.u32()?;
+        if flags & 1 != 0 {
+            self.u32()?;
+        }
+        Ok(())
+    }
+
+    /// Reads a constant expression up to and including its `end` opcode, returning the value if
+    /// the expression is a single `i32.const`.
+    pub(crate) fn const_expr(&mut self) -> Result<(&'a [u8], Option<i32>), Error> {
+        let start = self.pos;
+        let value = match self.byte()? {
+            0x41 => Some(self.i32()?),
+            // `global.get`
+            0x23 => {
+                self.u32()?;
+                None
+            }
+            opcode => return Err(Error::UnsupportedExpression(opcode)),
+        };
+        if self.byte()? != 0x0b {
+            return Err(Error::UnsupportedExpression(self.bytes[self.pos - 1]));
+        }
+        Ok((&self.bytes[start..self.pos], value))
+    }
+}
+
+pub(crate) fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_len_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128() {
+        for value in [0, 1, 127, 128, 624485, u32::MAX] {
+            let mut out = Vec::new();
+            write_u32(&mut out, value);
+            assert_eq!(Reader::new(&out).u32(), Ok(value));
+        }
+        for value in [0, -1, 63, 64, -64, -65, 1024, i32::MIN, i32::MAX] {
+            let mut out = Vec::new();
+            write_i32(&mut out, value);
+            assert_eq!(Reader::new(&out).i32(), Ok(value));
+        }
+        assert_eq!(Reader::new(&[0x7f]).i32(), Ok(-1));
+        assert_eq!(Reader::new(&[0x80, 0x7f]).i32(), Ok(-128));
+        assert_eq!(Reader::new(&[0x80, 0x80, 0x04]).i32(), Ok(65536));
+        assert_eq!(
+            Reader::new(&[0xff, 0xff, 0xff, 0xff, 0x1f]).u32(),
+            Err(Error::InvalidLeb)
+        );
+        assert_eq!(Reader::new(&[0x80]).u32(), Err(Error::UnexpectedEof));
+    }
+}
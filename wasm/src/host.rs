@@ -0,0 +1,75 @@
+/// Host functions declared by `near-sys`, which a contract can import from the `env` module.
+pub const HOST_FUNCTIONS: &[&str] = &[
+    "read_register",
+    "register_len",
+    "write_register",
+    "current_account_id",
+    "signer_account_id",
+    "signer_account_pk",
+    "predecessor_account_id",
+    "input",
+    "block_index",
+    "block_timestamp",
+    "epoch_height",
+    "storage_usage",
+    "account_balance",
+    "account_locked_balance",
+    "attached_deposit",
+    "prepaid_gas",
+    "used_gas",
+    "random_seed",
+    "sha256",
+    "keccak256",
+    "keccak512",
+    "ripemd160",
+    "ecrecover",
+    "value_return",
+    "panic",
+    "panic_utf8",
+    "log_utf8",
+    "log_utf16",
+    "abort",
+    "promise_create",
+    "promise_then",
+    "promise_and",
+    "promise_batch_create",
+    "promise_batch_then",
+    "promise_batch_action_create_account",
+    "promise_batch_action_deploy_contract",
+    "promise_batch_action_function_call",
+    "promise_batch_action_transfer",
+    "promise_batch_action_stake",
+    "promise_batch_action_add_key_with_full_access",
+    "promise_batch_action_add_key_with_function_call",
+    "promise_batch_action_delete_key",
+    "promise_batch_action_delete_account",
+    "promise_results_count",
+    "promise_result",
+    "promise_return",
+    "storage_write",
+    "storage_read",
+    "storage_remove",
+    "storage_has_key",
+    "storage_iter_prefix",
+    "storage_iter_range",
+    "storage_iter_next",
+    "validator_stake",
+    "validator_total_stake",
+    "alt_bn128_g1_multiexp",
+    "alt_bn128_g1_sum",
+    "alt_bn128_pairing_check",
+];
+
+/// Host functions which terminate the execution without a message. `env::abort` traps with
+/// `unreachable` instead, so an import of these usually means that `sys` is called directly.
+pub const ABORT_FUNCTIONS: &[&str] = &["panic", "abort"];
+
+/// Imports allowed by an audit without an explicit allowlist, which are all [`HOST_FUNCTIONS`]
+/// except the [`ABORT_FUNCTIONS`].
+pub fn default_allowed_imports() -> Vec<&'static str> {
+    HOST_FUNCTIONS
+        .iter()
+        .copied()
+        .filter(|name| !ABORT_FUNCTIONS.contains(name))
+        .collect()
+}
@@ -0,0 +1,706 @@
+//! Post-processing of contract binaries, to be run on the `.wasm` file produced by `cargo build`.
+//!
+//! The compiler leaves exports, custom sections and data in the binary which the runtime never
+//! uses, and which add to the size of every deployment. [`minify`] removes them, and [`Module`]
+//! exposes the individual steps along with checks of the imports and a size breakdown.
+//!
+//! This can be called from a build script or test, or through the `nesdie-wasm` binary:
+//!
+//! ```text
+//! nesdie-wasm target/wasm32-unknown-unknown/release/contract.wasm -o res/contract.wasm
+//! ```
+
+#![warn(missing_docs)]
+
+mod audit;
+mod data;
+mod encoding;
+/// List of the host functions a contract can import.
+pub mod host;
+
+pub use self::audit::Audit;
+
+use self::encoding::{write_len_bytes, write_u32, Reader};
+use std::fmt;
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: &[u8] = &[1, 0, 0, 0];
+
+const CUSTOM_SECTION: u8 = 0;
+const IMPORT_SECTION: u8 = 2;
+const EXPORT_SECTION: u8 = 7;
+const DATA_SECTION: u8 = 11;
+const DATA_COUNT_SECTION: u8 = 12;
+
+/// Subsection of the `name` custom section with the names of functions.
+const FUNCTION_NAMES: u8 = 1;
+
+/// Error parsing a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// File doesn't start with the wasm magic number.
+    InvalidMagic,
+    /// Module is not of version 1 of the binary format.
+    UnsupportedVersion,
+    /// Module ended in the middle of an item.
+    UnexpectedEof,
+    /// Integer is not a valid LEB128 encoding.
+    InvalidLeb,
+    /// Name is not valid UTF-8.
+    InvalidUtf8,
+    /// Section has trailing bytes after its items.
+    InvalidSectionLength(u8),
+    /// Import or export of an unknown kind.
+    UnknownKind(u8),
+    /// Constant expression with an opcode other than `i32.const` or `global.get`.
+    UnsupportedExpression(u8),
+    /// Data segment with unknown flags.
+    UnknownSegment(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidMagic => write!(f, "not a wasm module"),
+            Error::UnsupportedVersion => write!(f, "unsupported wasm version"),
+            Error::UnexpectedEof => write!(f, "unexpected end of module"),
+            Error::InvalidLeb => write!(f, "invalid LEB128 integer"),
+            Error::InvalidUtf8 => write!(f, "invalid UTF-8 name"),
+            Error::InvalidSectionLength(id) => write!(f, "invalid length of section {}", id),
+            Error::UnknownKind(kind) => write!(f, "unknown import or export kind {:#x}", kind),
+            Error::UnsupportedExpression(opcode) => {
+                write!(f, "unsupported opcode {:#x} in constant expression", opcode)
+            }
+            Error::UnknownSegment(flags) => write!(f, "unknown data segment flags {}", flags),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Kind of an imported or exported item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalKind {
+    /// Function.
+    Func,
+    /// Table.
+    Table,
+    /// Linear memory.
+    Memory,
+    /// Global variable.
+    Global,
+}
+
+impl ExternalKind {
+    fn from_byte(kind: u8) -> Result<Self, Error> {
+        match kind {
+            0 => Ok(ExternalKind::Func),
+            1 => Ok(ExternalKind::Table),
+            2 => Ok(ExternalKind::Memory),
+            3 => Ok(ExternalKind::Global),
+            kind => Err(Error::UnknownKind(kind)),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Item imported by a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    /// Module the item is imported from, `env` for host functions.
+    pub module: String,
+    /// Name of the item.
+    pub name: String,
+    /// Kind of the item.
+    pub kind: ExternalKind,
+}
+
+/// Item exported by a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Export {
+    /// Name the item is exported as, which is the method name for functions.
+    pub name: String,
+    /// Kind of the item.
+    pub kind: ExternalKind,
+    /// Index of the item in the index space of its kind.
+    pub index: u32,
+}
+
+/// Size of a single section, as part of a [`SizeReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionSize {
+    /// Name of the section, or of the custom section.
+    pub name: String,
+    /// Size in bytes, including the section id and length.
+    pub size: usize,
+}
+
+/// Section by section size breakdown of a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeReport {
+    /// Size in bytes of the whole module.
+    pub total: usize,
+    /// Sections in the order they appear in the module.
+    pub sections: Vec<SectionSize>,
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for section in &self.sections {
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>5.1}%",
+                section.name,
+                section.size,
+                section.size as f64 * 100.0 / self.total as f64
+            )?;
+        }
+        write!(f, "{:<24} {:>8}", "total", self.total)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Section {
+    id: u8,
+    payload: Vec<u8>,
+}
+
+impl Section {
+    fn encoded_len(&self) -> usize {
+        let mut header = Vec::new();
+        write_u32(&mut header, self.payload.len() as u32);
+        1 + header.len() + self.payload.len()
+    }
+
+    fn name(&self) -> String {
+        let name = match self.id {
+            CUSTOM_SECTION => {
+                return match Reader::new(&self.payload).name() {
+                    Ok(name) => format!("custom \"{}\"", name),
+                    Err(_) => "custom".to_string(),
+                };
+            }
+            1 => "type",
+            IMPORT_SECTION => "import",
+            3 => "function",
+            4 => "table",
+            5 => "memory",
+            6 => "global",
+            EXPORT_SECTION => "export",
+            8 => "start",
+            9 => "element",
+            10 => "code",
+            DATA_SECTION => "data",
+            DATA_COUNT_SECTION => "data count",
+            _ => "unknown",
+        };
+        name.to_string()
+    }
+}
+
+/// Parsed wasm module, which is only decoded down to the sections it processes.
+#[derive(Clone, Debug)]
+pub struct Module {
+    sections: Vec<Section>,
+}
+
+impl Module {
+    /// Parses the sections of the module in `bytes`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes);
+        if reader.bytes(4).map_err(|_| Error::InvalidMagic)? != MAGIC {
+            return Err(Error::InvalidMagic);
+        }
+        if reader.bytes(4).map_err(|_| Error::UnsupportedVersion)? != VERSION {
+            return Err(Error::UnsupportedVersion);
+        }
+        let mut sections = Vec::new();
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let payload = reader.len_bytes()?.to_vec();
+            sections.push(Section { id, payload });
+        }
+        Ok(Self { sections })
+    }
+
+    /// Encodes the module.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(VERSION);
+        for section in &self.sections {
+            out.push(section.id);
+            write_len_bytes(&mut out, &section.payload);
+        }
+        out
+    }
+
+    fn encoded_len(&self) -> usize {
+        MAGIC.len()
+            + VERSION.len()
+            + self
+                .sections
+                .iter()
+                .map(Section::encoded_len)
+                .sum::<usize>()
+    }
+
+    fn section(&self, id: u8) -> Option<&Section> {
+        self.sections.iter().find(|s| s.id == id)
+    }
+
+    fn section_mut(&mut self, id: u8) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|s| s.id == id)
+    }
+
+    /// Removes all custom sections, such as `name` and `producers`, which the runtime ignores.
+    /// Returns the names of the removed sections.
+    pub fn strip_custom_sections(&mut self) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.sections.retain(|section| {
+            if section.id == CUSTOM_SECTION {
+                removed.push(section.name());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Returns the imports of the module.
+    pub fn imports(&self) -> Result<Vec<Import>, Error> {
+        let section = match self.section(IMPORT_SECTION) {
+            Some(section) => section,
+            None => return Ok(Vec::new()),
+        };
+        let mut reader = Reader::new(&section.payload);
+        let count = reader.u32()?;
+        let mut imports = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let module = reader.name()?.to_string();
+            let name = reader.name()?.to_string();
+            let kind = ExternalKind::from_byte(reader.byte()?)?;
+            match kind {
+                ExternalKind::Func => {
+                    reader.u32()?;
+                }
+                ExternalKind::Table => {
+                    reader.byte()?;
+                    reader.limits()?;
+                }
+                ExternalKind::Memory => reader.limits()?,
+                ExternalKind::Global => {
+                    reader.bytes(2)?;
+                }
+            }
+            imports.push(Import { module, name, kind });
+        }
+        if !reader.is_empty() {
+            return Err(Error::InvalidSectionLength(IMPORT_SECTION));
+        }
+        Ok(imports)
+    }
+
+    /// Returns the imports which are not [host functions](host::HOST_FUNCTIONS) of the `env`
+    /// module. The runtime fails to instantiate a contract with any such import, which is
+    /// usually caused by a dependency linking against `std` or a missing `#[no_mangle]`
+    /// function.
+    pub fn unknown_imports(&self) -> Result<Vec<Import>, Error> {
+        Ok(self
+            .imports()?
+            .into_iter()
+            .filter(|import| {
+                import.module != "env"
+                    || import.kind != ExternalKind::Func
+                    || !host::HOST_FUNCTIONS.contains(&import.name.as_str())
+            })
+            .collect())
+    }
+
+    /// Returns the exports of the module.
+    pub fn exports(&self) -> Result<Vec<Export>, Error> {
+        let section = match self.section(EXPORT_SECTION) {
+            Some(section) => section,
+            None => return Ok(Vec::new()),
+        };
+        let mut reader = Reader::new(&section.payload);
+        let count = reader.u32()?;
+        let mut exports = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let name = reader.name()?.to_string();
+            let kind = ExternalKind::from_byte(reader.byte()?)?;
+            let index = reader.u32()?;
+            exports.push(Export { name, kind, index });
+        }
+        if !reader.is_empty() {
+            return Err(Error::InvalidSectionLength(EXPORT_SECTION));
+        }
+        Ok(exports)
+    }
+
+    /// Keeps only the exports for which `keep` returns `true`. Returns the removed exports.
+    pub fn retain_exports<F>(&mut self, mut keep: F) -> Result<Vec<Export>, Error>
+    where
+        F: FnMut(&Export) -> bool,
+    {
+        let (kept, removed): (Vec<_>, Vec<_>) = self.exports()?.into_iter().partition(&mut keep);
+        if removed.is_empty() {
+            return Ok(removed);
+        }
+        let mut payload = Vec::new();
+        write_u32(&mut payload, kept.len() as u32);
+        for export in &kept {
+            write_len_bytes(&mut payload, export.name.as_bytes());
+            payload.push(export.kind.to_byte());
+            write_u32(&mut payload, export.index);
+        }
+        if let Some(section) = self.section_mut(EXPORT_SECTION) {
+            section.payload = payload;
+        }
+        Ok(removed)
+    }
+
+    /// Removes the exports which the runtime doesn't use, which are all but the memory and the
+    /// functions. The linker exports globals such as `__data_end` and `__heap_base` by default.
+    ///
+    /// If `methods` is given, functions which are not in the list are removed as well, for
+    /// functions exported by dependencies.
+    pub fn remove_unused_exports(
+        &mut self,
+        methods: Option<&[&str]>,
+    ) -> Result<Vec<Export>, Error> {
+        self.retain_exports(|export| match export.kind {
+            ExternalKind::Memory => true,
+            ExternalKind::Func => match methods {
+                Some(methods) => methods.contains(&export.name.as_str()),
+                None => true,
+            },
+            ExternalKind::Table | ExternalKind::Global => false,
+        })
+    }
+
+    /// Removes duplicate data segments and the zero bytes at the edges of segments, which are
+    /// redundant since memory is zero-initialized. Returns the number of bytes saved.
+    ///
+    /// Segments are only changed if they don't overlap with other segments, and if all segments
+    /// are active with a constant offset, as the index of passive segments is referenced by code.
+    pub fn dedup_data(&mut self) -> Result<usize, Error> {
+        let section = match self.section_mut(DATA_SECTION) {
+            Some(section) => section,
+            None => return Ok(0),
+        };
+        let mut segments = data::parse(&section.payload)?;
+        let count = segments.len();
+        if !data::minimize(&mut segments) {
+            return Ok(0);
+        }
+        let payload = data::encode(&segments);
+        let saved = section.payload.len().saturating_sub(payload.len());
+        section.payload = payload;
+
+        if segments.len() != count {
+            if let Some(section) = self.section_mut(DATA_COUNT_SECTION) {
+                let mut payload = Vec::new();
+                write_u32(&mut payload, segments.len() as u32);
+                section.payload = payload;
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Returns the names of the functions from the `name` custom section, which is only present
+    /// if symbols were not stripped.
+    pub fn function_names(&self) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        for section in self.sections.iter().filter(|s| s.id == CUSTOM_SECTION) {
+            let mut reader = Reader::new(&section.payload);
+            if reader.name()? != "name" {
+                continue;
+            }
+            while !reader.is_empty() {
+                let id = reader.byte()?;
+                let subsection = reader.len_bytes()?;
+                if id != FUNCTION_NAMES {
+                    continue;
+                }
+                let mut reader = Reader::new(subsection);
+                for _ in 0..reader.u32()? {
+                    reader.u32()?;
+                    names.push(reader.name()?.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Returns the bytes of each data segment.
+    fn data(&self) -> Result<Vec<Vec<u8>>, Error> {
+        match self.section(DATA_SECTION) {
+            Some(section) => Ok(data::parse(&section.payload)?
+                .into_iter()
+                .map(data::Segment::into_data)
+                .collect()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the size of each section of the module.
+    pub fn size_report(&self) -> SizeReport {
+        SizeReport {
+            total: self.encoded_len(),
+            sections: self
+                .sections
+                .iter()
+                .map(|section| SectionSize {
+                    name: section.name(),
+                    size: section.encoded_len(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Strips custom sections and unused exports and deduplicates data segments of the module in
+/// `bytes`, as described by the methods of [`Module`]. If `methods` is given, only these
+/// functions remain exported.
+///
+/// # Example
+/// ```no_run
+/// let path = "target/wasm32-unknown-unknown/release/contract.wasm";
+/// let wasm = std::fs::read(path).unwrap();
+/// let minified = nesdie_wasm::minify(&wasm, None).unwrap();
+/// println!("{}", nesdie_wasm::Module::parse(&minified).unwrap().size_report());
+/// ```
+pub fn minify(bytes: &[u8], methods: Option<&[&str]>) -> Result<Vec<u8>, Error> {
+    let mut module = Module::parse(bytes)?;
+    module.strip_custom_sections();
+    module.remove_unused_exports(methods)?;
+    module.dedup_data()?;
+    Ok(module.encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+        out.push(id);
+        write_len_bytes(out, payload);
+    }
+
+    fn name(out: &mut Vec<u8>, name: &str) {
+        write_len_bytes(out, name.as_bytes());
+    }
+
+    /// Module with the sections of a contract, which are only checked for their encoding.
+    fn contract() -> Vec<u8> {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // (func (param i64)) and (func)
+        section(&mut wasm, 1, &[2, 0x60, 1, 0x7e, 0, 0x60, 0, 0]);
+
+        let mut imports = vec![3];
+        for (module, field) in [
+            ("env", "input"),
+            ("env", "value_return"),
+            ("env", "log_utf8"),
+        ] {
+            name(&mut imports, module);
+            name(&mut imports, field);
+            imports.extend_from_slice(&[0, 0]);
+        }
+        section(&mut wasm, IMPORT_SECTION, &imports);
+        section(&mut wasm, 3, &[1, 1]);
+        // Memory with a minimum of 17 pages.
+        section(&mut wasm, 5, &[1, 0, 17]);
+
+        let mut exports = vec![4];
+        for (export, kind, index) in [
+            ("memory", 2, 0),
+            ("transfer", 0, 3),
+            ("__data_end", 3, 0),
+            ("__heap_base", 3, 1),
+        ] {
+            name(&mut exports, export);
+            exports.extend_from_slice(&[kind, index]);
+        }
+        section(&mut wasm, EXPORT_SECTION, &exports);
+        section(&mut wasm, DATA_COUNT_SECTION, &[3]);
+        section(&mut wasm, 10, &[1, 2, 0, 0x0b]);
+
+        let mut data = vec![3];
+        for (offset, bytes) in [
+            (1024u16, &b"\0\0token\0"[..]),
+            (2048, &[0; 16][..]),
+            (1026, &b"token"[..]),
+        ] {
+            data.extend_from_slice(&[0, 0x41]);
+            data.extend_from_slice(&[(offset & 0x7f) as u8 | 0x80, (offset >> 7) as u8, 0x0b]);
+            write_len_bytes(&mut data, bytes);
+        }
+        section(&mut wasm, DATA_SECTION, &data);
+
+        let mut custom = Vec::new();
+        name(&mut custom, "producers");
+        custom.extend_from_slice(&[0; 10]);
+        section(&mut wasm, CUSTOM_SECTION, &custom);
+        wasm
+    }
+
+    #[test]
+    fn roundtrip() {
+        let wasm = contract();
+        let module = Module::parse(&wasm).unwrap();
+        assert_eq!(module.encode(), wasm);
+        assert_eq!(module.size_report().total, wasm.len());
+        assert_eq!(
+            Module::parse(b"\0asm\x02\0\0\0").unwrap_err(),
+            Error::UnsupportedVersion
+        );
+        assert_eq!(Module::parse(b"\0wasm").unwrap_err(), Error::InvalidMagic);
+        assert_eq!(
+            Module::parse(&wasm[..wasm.len() - 1]).unwrap_err(),
+            Error::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn minified() {
+        let wasm = contract();
+        let mut module = Module::parse(&wasm).unwrap();
+        assert_eq!(module.strip_custom_sections(), vec!["custom \"producers\""]);
+
+        let removed = module.remove_unused_exports(None).unwrap();
+        let removed: Vec<_> = removed.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(removed, ["__data_end", "__heap_base"]);
+        let exports: Vec<_> = module
+            .exports()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(exports, ["memory", "transfer"]);
+
+        // The duplicate "token" and the segment of zeros are removed, and the zeros around the
+        // first segment are trimmed.
+        assert_eq!(module.dedup_data().unwrap(), 36);
+        let data = module.section(DATA_SECTION).unwrap();
+        assert_eq!(
+            data.payload,
+            [1, 0, 0x41, 0x82, 0x08, 0x0b, 5, b't', b'o', b'k', b'e', b'n']
+        );
+        assert_eq!(module.section(DATA_COUNT_SECTION).unwrap().payload, [1]);
+
+        let minified = module.encode();
+        assert_eq!(minify(&wasm, None).unwrap(), minified);
+        assert_eq!(Module::parse(&minified).unwrap().encode(), minified);
+
+        let mut module = Module::parse(&minified).unwrap();
+        let removed = module.remove_unused_exports(Some(&["deposit"])).unwrap();
+        assert_eq!(removed[0].name, "transfer");
+    }
+
+    #[test]
+    fn imports() {
+        let module = Module::parse(&contract()).unwrap();
+        let imports: Vec<_> = module
+            .imports()
+            .unwrap()
+            .into_iter()
+            .map(|i| i.name)
+            .collect();
+        assert_eq!(imports, ["input", "value_return", "log_utf8"]);
+        assert!(module.unknown_imports().unwrap().is_empty());
+
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        let mut imports = vec![2];
+        for (module, field) in [
+            ("env", "storage_write"),
+            ("wasi_snapshot_preview1", "fd_write"),
+        ] {
+            name(&mut imports, module);
+            name(&mut imports, field);
+            imports.extend_from_slice(&[0, 0]);
+        }
+        section(&mut wasm, IMPORT_SECTION, &imports);
+        let unknown = Module::parse(&wasm).unwrap().unknown_imports().unwrap();
+        assert_eq!(
+            unknown,
+            [Import {
+                module: "wasi_snapshot_preview1".to_string(),
+                name: "fd_write".to_string(),
+                kind: ExternalKind::Func,
+            }]
+        );
+    }
+
+    #[test]
+    fn audit() {
+        let mut wasm = contract();
+        let module = Module::parse(&wasm).unwrap();
+        let audit = module
+            .audit(&["input", "value_return", "log_utf8"])
+            .unwrap();
+        assert!(audit.is_clean(), "{}", audit);
+        let audit = module.audit(&["input", "value_return"]).unwrap();
+        assert_eq!(audit.disallowed_imports[0].name, "log_utf8");
+        // Aborts through host functions are flagged unless allowed explicitly.
+        let allowed = host::default_allowed_imports();
+        assert!(module.audit(&allowed).unwrap().is_clean());
+        assert!(!allowed.contains(&"panic") && !allowed.contains(&"abort"));
+
+        let mut names = Vec::new();
+        name(&mut names, "name");
+        let mut functions = vec![2, 3];
+        name(&mut functions, "transfer");
+        functions.push(4);
+        name(&mut functions, "core::fmt::write::h0123");
+        names.push(FUNCTION_NAMES);
+        write_len_bytes(&mut names, &functions);
+        section(&mut wasm, CUSTOM_SECTION, &names);
+
+        let mut data = vec![1, 0, 0x41, 0, 0x0b];
+        write_len_bytes(&mut data, b"\x03\0src/lib.rs\0\0\0token");
+        let mut module = Module::parse(&wasm).unwrap();
+        module.section_mut(DATA_SECTION).unwrap().payload = data;
+
+        assert_eq!(
+            module.function_names().unwrap(),
+            ["transfer", "core::fmt::write::h0123"]
+        );
+        let audit = module
+            .audit(&["input", "value_return", "log_utf8"])
+            .unwrap();
+        assert_eq!(audit.fmt_functions, ["core::fmt::write::h0123"]);
+        assert_eq!(audit.paths, ["src/lib.rs"]);
+        assert!(!audit.is_clean());
+    }
+
+    #[test]
+    fn size_report() {
+        let report = Module::parse(&contract()).unwrap().size_report();
+        let names: Vec<_> = report.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "type",
+                "import",
+                "function",
+                "memory",
+                "export",
+                "data count",
+                "code",
+                "data",
+                "custom \"producers\""
+            ]
+        );
+        let sections: usize = report.sections.iter().map(|s| s.size).sum();
+        assert_eq!(sections + 8, report.total);
+        assert!(report
+            .to_string()
+            .ends_with(&format!("{:<24} {:>8}", "total", report.total)[..]));
+    }
+}
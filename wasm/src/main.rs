@@ -0,0 +1,127 @@
+use nesdie_wasm::Module;
+use std::process::exit;
+
+const USAGE: &str = "\
+Strips and minimizes a contract binary, and checks that it only imports NEAR host functions.
+
+Usage: nesdie-wasm <input.wasm> [-o <output.wasm>] [--method <name>]...
+       nesdie-wasm <input.wasm> --audit [--allow <import>]...
+
+Options:
+  -o, --output <path>  Path of the minified binary, defaults to overwriting the input
+  --method <name>      Keep only the given exported methods, can be repeated
+  --report             Only print the size breakdown, without changing the binary
+  --audit              Only check for `core::fmt` functions, source paths and disallowed imports
+  --allow <import>     Host function allowed by `--audit`, defaults to all of near-sys except
+                       `panic` and `abort`";
+
+struct Args {
+    input: String,
+    output: Option<String>,
+    methods: Vec<String>,
+    report: bool,
+    audit: bool,
+    allowed_imports: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut methods = Vec::new();
+    let mut report = false;
+    let mut audit = false;
+    let mut allowed_imports = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(args.next().ok_or("missing output path")?),
+            "--method" => methods.push(args.next().ok_or("missing method name")?),
+            "--report" => report = true,
+            "--audit" => audit = true,
+            "--allow" => allowed_imports.push(args.next().ok_or("missing import name")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Args {
+        input: input.ok_or("missing input path")?,
+        output,
+        methods,
+        report,
+        audit,
+        allowed_imports,
+    })
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let bytes = std::fs::read(&args.input).map_err(|e| format!("{}: {}", args.input, e))?;
+    let mut module = Module::parse(&bytes).map_err(|e| format!("{}: {}", args.input, e))?;
+
+    let unknown = module.unknown_imports().map_err(|e| e.to_string())?;
+    if !unknown.is_empty() {
+        for import in &unknown {
+            eprintln!("unknown import {}::{}", import.module, import.name);
+        }
+        return Err("the runtime only provides the host functions of near-sys".into());
+    }
+
+    if args.audit {
+        let allowed: Vec<_> = if args.allowed_imports.is_empty() {
+            nesdie_wasm::host::default_allowed_imports()
+        } else {
+            args.allowed_imports.iter().map(String::as_str).collect()
+        };
+        let audit = module.audit(&allowed).map_err(|e| e.to_string())?;
+        println!("{}", audit);
+        return if audit.is_clean() {
+            Ok(())
+        } else {
+            Err("audit failed".into())
+        };
+    }
+
+    if args.report {
+        println!("{}", module.size_report());
+        return Ok(());
+    }
+
+    for name in module.strip_custom_sections() {
+        println!("removed section {}", name);
+    }
+    let methods: Vec<_> = args.methods.iter().map(String::as_str).collect();
+    let methods = if methods.is_empty() {
+        None
+    } else {
+        Some(&methods[..])
+    };
+    for export in module
+        .remove_unused_exports(methods)
+        .map_err(|e| e.to_string())?
+    {
+        println!("removed export {}", export.name);
+    }
+    let saved = module.dedup_data().map_err(|e| e.to_string())?;
+    println!("removed {} bytes of data", saved);
+
+    let minified = module.encode();
+    println!("\n{}", module.size_report());
+    println!("{} -> {} bytes", bytes.len(), minified.len());
+
+    let output = args.output.as_ref().unwrap_or(&args.input);
+    std::fs::write(output, minified).map_err(|e| format!("{}: {}", output, e))
+}
+
+fn main() {
+    let result = parse_args()
+        .map_err(|e| format!("{}\n\n{}", e, USAGE))
+        .and_then(run);
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        exit(1);
+    }
+}